@@ -0,0 +1,189 @@
+//! Keyboard brightness that follows the ambient light.
+
+use crate::error::{Error, Result};
+use crate::types::Brightness;
+
+use std::str::FromStr;
+use std::time::Duration;
+
+/// A brightness pinned to an ambient light level, e.g. 20% at 500 lux.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurvePoint {
+    pub lux: f64,
+    /// Brightness in percent.
+    pub brightness: f64,
+}
+
+/// Parses `lux=percent`, e.g. `500=20`.
+impl FromStr for CurvePoint {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: &str| Error::InvalidCurvePoint {
+            input: s.to_string(),
+            reason: reason.to_string(),
+        };
+        let (lux, brightness) = s
+            .split_once('=')
+            .ok_or_else(|| invalid("expected lux=percent (e.g., 500=20)"))?;
+        let lux: f64 = lux
+            .trim()
+            .parse()
+            .map_err(|_| invalid("the light level must be a number"))?;
+        let brightness: f64 = brightness
+            .trim()
+            .trim_end_matches('%')
+            .parse()
+            .map_err(|_| invalid("the brightness must be a number"))?;
+        if !(lux.is_finite() && lux >= 0.0) {
+            return Err(invalid("the light level can't be negative"));
+        }
+        if !(0.0..=100.0).contains(&brightness) {
+            return Err(invalid("the brightness must be between 0 and 100"));
+        }
+        Ok(Self { lux, brightness })
+    }
+}
+
+/// Brightness along a range of light levels. Between two points it's
+/// interpolated on a log scale, since the eye judges light by ratios: going
+/// from 10 to 100 lux looks like as big a step as 100 to 1000.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrightnessCurve {
+    points: Vec<CurvePoint>,
+}
+
+impl BrightnessCurve {
+    pub fn new(mut points: Vec<CurvePoint>) -> Self {
+        points.sort_by(|a, b| a.lux.total_cmp(&b.lux));
+        Self { points }
+    }
+
+    /// The points, darkest first.
+    pub fn points(&self) -> &[CurvePoint] {
+        &self.points
+    }
+
+    /// The brightness in percent at `lux`, held at the end points outside
+    /// the curve.
+    pub fn brightness(&self, lux: f64) -> f64 {
+        let (Some(first), Some(last)) = (self.points.first(), self.points.last()) else {
+            return 100.0;
+        };
+        if lux <= first.lux {
+            return first.brightness;
+        }
+        if lux >= last.lux {
+            return last.brightness;
+        }
+        let position = |lux: f64| (1.0 + lux).ln();
+        self.points
+            .windows(2)
+            .find(|pair| lux <= pair[1].lux)
+            .map_or(last.brightness, |pair| {
+                let span = position(pair[1].lux) - position(pair[0].lux);
+                let t = match span > 0.0 {
+                    true => (position(lux) - position(pair[0].lux)) / span,
+                    false => 1.0,
+                };
+                pair[0].brightness + (pair[1].brightness - pair[0].brightness) * t
+            })
+    }
+}
+
+/// Turns light readings into a smoothed brightness, so a hand passing over
+/// the sensor doesn't make the keyboard flash.
+#[derive(Debug, Clone)]
+pub struct AutoBrightness {
+    pub curve: BrightnessCurve,
+    /// Lowest brightness it goes to, in percent.
+    pub min: u8,
+    /// Highest brightness it goes to, in percent.
+    pub max: u8,
+    /// Time constant of the smoothing: after it has passed, the brightness
+    /// has moved about two thirds of the way to a new target.
+    pub smoothing: Duration,
+    level: Option<f64>,
+}
+
+impl AutoBrightness {
+    pub fn new(curve: BrightnessCurve, min: u8, max: u8, smoothing: Duration) -> Self {
+        Self {
+            curve,
+            min: min.min(100),
+            max: max.clamp(min.min(100), 100),
+            smoothing,
+            level: None,
+        }
+    }
+
+    /// Feeds in a reading, `elapsed` after the previous one, and returns the
+    /// brightness to use. The first reading is followed right away.
+    pub fn update(&mut self, lux: f64, elapsed: Duration) -> Brightness {
+        let target = self
+            .curve
+            .brightness(lux)
+            .clamp(self.min as f64, self.max as f64);
+        let level = match self.level {
+            Some(level) if !self.smoothing.is_zero() => {
+                let t = 1.0 - (-elapsed.as_secs_f64() / self.smoothing.as_secs_f64()).exp();
+                level + (target - level) * t
+            }
+            _ => target,
+        };
+        self.level = Some(level);
+        Brightness::new(level.round() as u8).unwrap_or(Brightness::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve() -> BrightnessCurve {
+        let points = ["1000=10", "0=100", "9=70%"];
+        BrightnessCurve::new(points.iter().map(|p| p.parse().unwrap()).collect())
+    }
+
+    #[test]
+    fn parses_curve_points() {
+        assert_eq!(
+            "500 = 20%".parse::<CurvePoint>().unwrap(),
+            CurvePoint {
+                lux: 500.0,
+                brightness: 20.0
+            }
+        );
+        assert!("500".parse::<CurvePoint>().is_err());
+        assert!("-1=20".parse::<CurvePoint>().is_err());
+        assert!("500=120".parse::<CurvePoint>().is_err());
+    }
+
+    #[test]
+    fn the_curve_is_logarithmic_between_points() {
+        let curve = curve();
+        assert_eq!(curve.brightness(0.0), 100.0);
+        assert_eq!(curve.brightness(9.0), 70.0);
+        assert_eq!(curve.brightness(5000.0), 10.0);
+        // 99 lux is halfway from 9 to 999 in (1 + lux) ratios
+        assert!((curve.brightness(99.0) - 40.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn brightness_is_smoothed_and_clamped() {
+        let mut auto = AutoBrightness::new(curve(), 20, 80, Duration::from_secs(2));
+        assert_eq!(
+            auto.update(0.0, Duration::ZERO),
+            Brightness::new(80).unwrap()
+        );
+        assert_eq!(
+            auto.update(5000.0, Duration::ZERO),
+            Brightness::new(80).unwrap()
+        );
+        // one time constant moves about two thirds of the way to 20%
+        assert_eq!(
+            auto.update(5000.0, Duration::from_secs(2)),
+            Brightness::new(42).unwrap()
+        );
+    }
+}
@@ -0,0 +1,432 @@
+use crate::config::Config;
+use crate::encoder::{Device, DevicePayload, CHARACTER_DEVICE, CHARACTER_DEVICE_STATIC};
+use crate::error::{Error, Result};
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Somewhere device payloads can be written to.
+pub trait Backend: Send {
+    fn write(&mut self, payload: &DevicePayload) -> Result<()>;
+}
+
+pub const DEVICE_ENV: &str = "ACER_RGB_DEVICE";
+pub const STATIC_DEVICE_ENV: &str = "ACER_RGB_STATIC_DEVICE";
+
+/// Where the two character devices live.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DevicePaths {
+    pub dynamic: PathBuf,
+    pub static_: PathBuf,
+}
+
+impl Default for DevicePaths {
+    fn default() -> Self {
+        Self {
+            dynamic: PathBuf::from(CHARACTER_DEVICE),
+            static_: PathBuf::from(CHARACTER_DEVICE_STATIC),
+        }
+    }
+}
+
+impl DevicePaths {
+    /// Picks each path from the first of `device`/`static_device` (e.g. flags),
+    /// the `ACER_RGB_DEVICE`/`ACER_RGB_STATIC_DEVICE` environment variables
+    /// and the config file. When only one of them is given, the other is the
+    /// node of the same instance, e.g. `acer-gkbbl-static-1` for
+    /// `acer-gkbbl-1`.
+    ///
+    /// Without any path given, the first instance found with
+    /// [`DevicePaths::discover`] in `/dev` is used, or the default `-0` nodes
+    /// if there is none. A path given that doesn't exist falls back to
+    /// discovery with a warning, and is an error if nothing is found.
+    pub fn resolve(
+        device: Option<PathBuf>,
+        static_device: Option<PathBuf>,
+        config: &Config,
+    ) -> Result<Self> {
+        Self::resolve_in("/dev", device, static_device, config)
+    }
+
+    fn resolve_in(
+        dev: impl AsRef<Path>,
+        device: Option<PathBuf>,
+        static_device: Option<PathBuf>,
+        config: &Config,
+    ) -> Result<Self> {
+        let env = |name| std::env::var_os(name).map(|path| (PathBuf::from(path), name));
+        let dynamic = device
+            .map(|path| (path, "--device"))
+            .or_else(|| env(DEVICE_ENV))
+            .or_else(|| config.device.clone().map(|path| (path, "the config file")));
+        let static_ = static_device
+            .map(|path| (path, "--static-device"))
+            .or_else(|| env(STATIC_DEVICE_ENV))
+            .or_else(|| {
+                config
+                    .static_device
+                    .clone()
+                    .map(|path| (path, "the config file"))
+            });
+
+        if dynamic.is_none() && static_.is_none() {
+            return Ok(Self::discover(dev).unwrap_or_default());
+        }
+        if let Some((path, origin)) = dynamic
+            .iter()
+            .chain(&static_)
+            .find(|(path, _)| !path.exists())
+        {
+            let err = Error::DeviceNotFound {
+                path: path.display().to_string(),
+                origin: origin.to_string(),
+            };
+            let found = Self::discover(dev).ok_or(err)?;
+            eprintln!(
+                "Device {} from {} doesn't exist, using {} and {} instead",
+                path.display(),
+                origin,
+                found.dynamic.display(),
+                found.static_.display()
+            );
+            return Ok(found);
+        }
+
+        let dynamic = dynamic.map(|(path, _)| path);
+        let static_ = static_.map(|(path, _)| path);
+        let defaults = Self::default();
+        Ok(Self {
+            dynamic: dynamic
+                .clone()
+                .or_else(|| Self::pair_of(static_.as_ref()?, Device::Dynamic))
+                .unwrap_or(defaults.dynamic),
+            static_: static_
+                .or_else(|| Self::pair_of(dynamic.as_ref()?, Device::Static))
+                .unwrap_or(defaults.static_),
+        })
+    }
+
+    /// The `device` node of the instance `path` is a node of, next to it.
+    fn pair_of(path: &Path, device: Device) -> Option<PathBuf> {
+        let name = path.file_name()?.to_str()?;
+        let instance = name
+            .strip_prefix("acer-gkbbl-static-")
+            .or_else(|| name.strip_prefix("acer-gkbbl-"))?;
+        instance.parse::<u32>().ok()?;
+        Some(path.with_file_name(match device {
+            Device::Dynamic => format!("acer-gkbbl-{}", instance),
+            Device::Static => format!("acer-gkbbl-static-{}", instance),
+        }))
+    }
+
+    /// Looks for `acer-gkbbl-<n>` and `acer-gkbbl-static-<n>` nodes in `dir`,
+    /// preferring the lowest instance that has both.
+    pub fn discover(dir: impl AsRef<Path>) -> Option<Self> {
+        let mut instances: Vec<u32> = std::fs::read_dir(dir.as_ref())
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name();
+                // static nodes don't parse, their instance is after "static-"
+                name.to_str()?.strip_prefix("acer-gkbbl-")?.parse().ok()
+            })
+            .collect();
+        instances.sort();
+
+        instances.into_iter().find_map(|instance| {
+            let paths = Self {
+                dynamic: dir.as_ref().join(format!("acer-gkbbl-{}", instance)),
+                static_: dir.as_ref().join(format!("acer-gkbbl-static-{}", instance)),
+            };
+            paths.static_.exists().then_some(paths)
+        })
+    }
+
+    pub fn get(&self, device: Device) -> &Path {
+        match device {
+            Device::Dynamic => &self.dynamic,
+            Device::Static => &self.static_,
+        }
+    }
+}
+
+/// The character devices created by the kernel module.
+///
+/// Each device is opened on the first payload written to it.
+pub struct CharDevice {
+    paths: DevicePaths,
+    device: Option<File>,
+    device_static: Option<File>,
+}
+
+impl CharDevice {
+    pub fn new() -> Self {
+        Self::with_paths(DevicePaths::default())
+    }
+
+    pub fn with_paths(paths: DevicePaths) -> Self {
+        Self {
+            paths,
+            device: None,
+            device_static: None,
+        }
+    }
+
+    fn open_device(device: &Path) -> Result<File> {
+        OpenOptions::new()
+            .write(true)
+            .open(device)
+            .map_err(|source| Error::OpenDevice {
+                path: device.display().to_string(),
+                source,
+            })
+    }
+}
+
+impl Default for CharDevice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Backend for CharDevice {
+    fn write(&mut self, payload: &DevicePayload) -> Result<()> {
+        let file = match payload.device {
+            Device::Dynamic => &mut self.device,
+            Device::Static => &mut self.device_static,
+        };
+        let path = self.paths.get(payload.device);
+        if file.is_none() {
+            *file = Some(Self::open_device(path)?);
+        }
+        file.as_mut()
+            .unwrap()
+            .write_all(&payload.payload)
+            .map_err(|source| Error::WriteDevice {
+                path: path.display().to_string(),
+                source,
+            })
+    }
+}
+
+/// Keeps every payload in memory instead of writing it anywhere.
+///
+/// Clones share the same log, so a clone can be handed to a
+/// [`KeyboardController`](crate::KeyboardController) and inspected afterwards.
+#[derive(Debug, Clone, Default)]
+pub struct Recorder {
+    payloads: Arc<Mutex<Vec<DevicePayload>>>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every payload written so far, oldest first.
+    pub fn payloads(&self) -> Vec<DevicePayload> {
+        self.payloads.lock().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        self.payloads.lock().unwrap().clear();
+    }
+}
+
+impl Backend for Recorder {
+    fn write(&mut self, payload: &DevicePayload) -> Result<()> {
+        self.payloads.lock().unwrap().push(payload.clone());
+        Ok(())
+    }
+}
+
+/// Appends the payload bytes to a regular file or FIFO, exactly as they would
+/// be written to the character devices.
+///
+/// Payloads for both devices go to the same file. Static payloads are 4 bytes
+/// and dynamic ones 16, so they can be told apart by their length.
+pub struct FileBackend {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl FileBackend {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            file: None,
+        }
+    }
+}
+
+impl Backend for FileBackend {
+    fn write(&mut self, payload: &DevicePayload) -> Result<()> {
+        let path = self.path.display().to_string();
+        if self.file.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .map_err(|source| Error::OpenDevice {
+                    path: path.clone(),
+                    source,
+                })?;
+            self.file = Some(file);
+        }
+
+        self.file
+            .as_mut()
+            .unwrap()
+            .write_all(&payload.payload)
+            .map_err(|source| Error::WriteDevice { path, source })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Brightness, Direction, KeyboardController, LightingMode, LightingState, Speed, Zone, RGB,
+    };
+
+    fn dev(nodes: &[&str]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for node in nodes {
+            File::create(dir.path().join(node)).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn discover_pairs_nodes_of_the_same_instance() {
+        let dir = dev(&[
+            "acer-gkbbl-0",
+            "acer-gkbbl-static-1",
+            "acer-gkbbl-10",
+            "acer-gkbbl-static-10",
+            "acer-gkbbl-2",
+            "acer-gkbbl-static-2",
+        ]);
+        let found = DevicePaths::discover(dir.path()).unwrap();
+        // 0 has no static node, and 2 sorts before 10
+        assert_eq!(found.dynamic, dir.path().join("acer-gkbbl-2"));
+        assert_eq!(found.static_, dir.path().join("acer-gkbbl-static-2"));
+    }
+
+    #[test]
+    fn missing_paths_fall_back_to_discovery() {
+        let dir = dev(&["acer-gkbbl-1", "acer-gkbbl-static-1"]);
+        let typo = dir.path().join("acer-gkbbl-O");
+        let paths =
+            DevicePaths::resolve_in(dir.path(), Some(typo.clone()), None, &Config::default());
+        assert_eq!(
+            paths.unwrap(),
+            DevicePaths {
+                dynamic: dir.path().join("acer-gkbbl-1"),
+                static_: dir.path().join("acer-gkbbl-static-1"),
+            }
+        );
+
+        let empty = dev(&[]);
+        let err = DevicePaths::resolve_in(empty.path(), Some(typo), None, &Config::default());
+        assert!(matches!(err, Err(Error::DeviceNotFound { .. })));
+    }
+
+    #[test]
+    fn one_path_picks_the_other_node_of_its_instance() {
+        let dir = dev(&[
+            "acer-gkbbl-0",
+            "acer-gkbbl-static-0",
+            "acer-gkbbl-1",
+            "acer-gkbbl-static-1",
+        ]);
+        let paths = DevicePaths::resolve_in(
+            dir.path(),
+            Some(dir.path().join("acer-gkbbl-1")),
+            None,
+            &Config::default(),
+        )
+        .unwrap();
+        assert_eq!(paths.static_, dir.path().join("acer-gkbbl-static-1"));
+
+        let config = Config {
+            static_device: Some(dir.path().join("acer-gkbbl-static-1")),
+            ..Config::default()
+        };
+        let paths = DevicePaths::resolve_in(dir.path(), None, None, &config).unwrap();
+        assert_eq!(paths.dynamic, dir.path().join("acer-gkbbl-1"));
+
+        let config = Config {
+            device: Some(dir.path().join("acer-gkbbl-0")),
+            static_device: Some(dir.path().join("acer-gkbbl-static-1")),
+        };
+        let paths = DevicePaths::resolve_in(dir.path(), None, None, &config).unwrap();
+        assert_eq!(paths.dynamic, dir.path().join("acer-gkbbl-0"));
+        assert_eq!(paths.static_, dir.path().join("acer-gkbbl-static-1"));
+    }
+
+    fn payload(device: Device, hex: &str) -> DevicePayload {
+        let payload = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+        DevicePayload { device, payload }
+    }
+
+    #[test]
+    fn recorder_sees_static_and_dynamic_payloads() {
+        let recorder = Recorder::new();
+        let mut controller = KeyboardController::with_backend(recorder.clone());
+
+        let red = RGB::new(255, 0, 0);
+        let zones = vec![(Zone::new(1).unwrap(), red), (Zone::new(3).unwrap(), red)];
+        controller
+            .apply(&LightingState::new_static(
+                zones,
+                Brightness::new(60).unwrap(),
+            ))
+            .unwrap();
+        assert_eq!(
+            recorder.payloads(),
+            vec![
+                payload(Device::Static, "01ff0000"),
+                payload(Device::Static, "04ff0000"),
+                payload(Device::Dynamic, "00003c00000000000001000000000000"),
+            ]
+        );
+
+        recorder.clear();
+        controller
+            .apply(&LightingState::new_dynamic(
+                LightingMode::Wave,
+                RGB::new(0x10, 0x20, 0x30),
+                Speed::new(7).unwrap(),
+                Brightness::MAX,
+                Direction::RightToLeft,
+            ))
+            .unwrap();
+        assert_eq!(
+            recorder.payloads(),
+            vec![payload(Device::Dynamic, "03076408011020300001000000000000")]
+        );
+    }
+
+    #[test]
+    fn file_backend_appends_raw_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("payloads");
+        std::fs::write(&path, [0xaa]).unwrap();
+
+        let mut backend = FileBackend::new(&path);
+        let static_ = payload(Device::Static, "01ff0000");
+        let dynamic = payload(Device::Dynamic, "00006400000000000001000000000000");
+        backend.write(&static_).unwrap();
+        backend.write(&dynamic).unwrap();
+        assert_eq!(
+            std::fs::read(&path).unwrap(),
+            [vec![0xaa], static_.payload, dynamic.payload].concat()
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Showing the time of day on the zones.
+
+use crate::effects::Frame;
+use crate::types::RGB;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A wall clock time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeOfDay {
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl TimeOfDay {
+    /// Seconds since midnight.
+    pub fn seconds(&self) -> u32 {
+        self.hour as u32 * 3600 + self.minute as u32 * 60 + self.second as u32
+    }
+
+    /// Time left until the minute changes.
+    pub fn until_next_minute(&self) -> Duration {
+        Duration::from_secs(60 - self.second.min(59) as u64)
+    }
+}
+
+impl std::fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second)
+    }
+}
+
+/// Parses `hh:mm` or `hh:mm:ss`.
+impl FromStr for TimeOfDay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{}' is not a time, use hh:mm or hh:mm:ss", s);
+        let parts: Vec<u8> = s
+            .trim()
+            .split(':')
+            .map(|part| part.parse().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+        let time = match parts[..] {
+            [hour, minute] => TimeOfDay {
+                hour,
+                minute,
+                second: 0,
+            },
+            [hour, minute, second] => TimeOfDay {
+                hour,
+                minute,
+                second,
+            },
+            _ => return Err(invalid()),
+        };
+        match time.hour < 24 && time.minute < 60 && time.second < 60 {
+            true => Ok(time),
+            false => Err(invalid()),
+        }
+    }
+}
+
+/// Where the time comes from.
+pub trait Clock {
+    fn now(&self) -> TimeOfDay;
+}
+
+/// The system clock in the local time zone.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalClock;
+
+impl Clock for LocalClock {
+    fn now(&self) -> TimeOfDay {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs()) as libc::time_t;
+        // SAFETY: an all-zero `tm` is valid, and both pointers outlive the call
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        let local = unsafe { !libc::localtime_r(&now, &mut tm).is_null() };
+        if !local {
+            // no time zone to be had, UTC it is
+            let seconds = now as u64 % 86400;
+            return TimeOfDay {
+                hour: (seconds / 3600) as u8,
+                minute: (seconds / 60 % 60) as u8,
+                second: (seconds % 60) as u8,
+            };
+        }
+        TimeOfDay {
+            hour: tm.tm_hour as u8,
+            minute: tm.tm_min as u8,
+            // leap seconds show up as 60
+            second: tm.tm_sec.min(59) as u8,
+        }
+    }
+}
+
+/// Always the same time, for previews and tests.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub TimeOfDay);
+
+impl Clock for FixedClock {
+    fn now(&self) -> TimeOfDay {
+        self.0
+    }
+}
+
+/// How the time is drawn.
+#[derive(Debug, Clone, Copy, Default, ValueEnum, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClockFace {
+    /// The hour (1-12) as four bits, most significant on zone 1.
+    #[default]
+    BinaryHour,
+    /// The minute in five minute steps (0-11) as four bits.
+    BinaryMinute,
+    /// Every zone in a hue that goes around the color wheel once a day.
+    Hue,
+}
+
+/// Draws times on the zones.
+#[derive(Debug, Clone, Copy)]
+pub struct ClockDisplay {
+    pub face: ClockFace,
+    /// Color of set bits.
+    pub on: RGB,
+    /// Color of cleared bits.
+    pub off: RGB,
+}
+
+impl ClockDisplay {
+    pub fn frame(&self, time: TimeOfDay) -> Frame {
+        match self.face {
+            ClockFace::BinaryHour => self.binary(match time.hour % 12 {
+                0 => 12,
+                hour => hour,
+            }),
+            ClockFace::BinaryMinute => self.binary(time.minute / 5),
+            ClockFace::Hue => {
+                // whole minutes, so it only changes when the minute does
+                let minutes = time.seconds() / 60;
+                [RGB::from_hsv(360.0 * minutes as f64 / 1440.0, 1.0, 1.0); 4]
+            }
+        }
+    }
+
+    fn binary(&self, value: u8) -> Frame {
+        std::array::from_fn(|zone| match value >> (3 - zone) & 1 {
+            1 => self.on,
+            _ => self.off,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ON: RGB = RGB::new(255, 255, 255);
+    const OFF: RGB = RGB::BLACK;
+
+    fn show(face: ClockFace, time: &str) -> Frame {
+        let display = ClockDisplay {
+            face,
+            on: ON,
+            off: OFF,
+        };
+        display.frame(FixedClock(time.parse().unwrap()).now())
+    }
+
+    #[test]
+    fn parses_times() {
+        let time: TimeOfDay = "07:05".parse().unwrap();
+        assert_eq!(time.to_string(), "07:05:00");
+        assert_eq!(time.until_next_minute(), Duration::from_secs(60));
+        assert!("24:00".parse::<TimeOfDay>().is_err());
+        assert!("12:60".parse::<TimeOfDay>().is_err());
+        assert!("12".parse::<TimeOfDay>().is_err());
+    }
+
+    #[test]
+    fn binary_faces_show_four_bits() {
+        assert_eq!(show(ClockFace::BinaryHour, "10:15"), [ON, OFF, ON, OFF]);
+        assert_eq!(show(ClockFace::BinaryHour, "21:00"), [ON, OFF, OFF, ON]);
+        // midnight and noon are 12
+        assert_eq!(show(ClockFace::BinaryHour, "00:30"), [ON, ON, OFF, OFF]);
+        assert_eq!(show(ClockFace::BinaryMinute, "10:59"), [ON, OFF, ON, ON]);
+    }
+
+    #[test]
+    fn the_hue_goes_around_once_a_day() {
+        assert_eq!(show(ClockFace::Hue, "00:00"), [RGB::new(255, 0, 0); 4]);
+        assert_eq!(show(ClockFace::Hue, "08:00"), [RGB::new(0, 255, 0); 4]);
+        assert_eq!(show(ClockFace::Hue, "16:00:59"), [RGB::new(0, 0, 255); 4]);
+    }
+}
@@ -0,0 +1,40 @@
+use crate::{apply_state, current_state, Args};
+use acer_rgb::ambient::AutoBrightness;
+use acer_rgb::sensors::light::LightSensor;
+
+use color_eyre::eyre::Result;
+use std::time::{Duration, Instant};
+
+// bright in the dark, off in daylight
+pub const DEFAULT_CURVE: [&str; 4] = ["0=100", "20=70", "300=30", "1000=0"];
+
+// reads `sensor` every `interval` and reapplies the current lighting whenever
+// the brightness it calls for changes, so colors set meanwhile are kept
+pub fn run(
+    args: &Args,
+    sensor: &LightSensor,
+    mut auto: AutoBrightness,
+    interval: Duration,
+) -> Result<()> {
+    println!("Following {} ({})", sensor.name, sensor.id);
+    let mut last_read = Instant::now();
+    let mut shown = None;
+
+    loop {
+        let lux = sensor.read()?;
+        let brightness = auto.update(lux, last_read.elapsed());
+        last_read = Instant::now();
+
+        if shown != Some(brightness) {
+            if args.dry_run {
+                println!("{:>9.1} lux  {:>3}%", lux, brightness.get());
+                shown = Some(brightness);
+            } else if let Some(mut state) = current_state(args)? {
+                state.brightness = brightness;
+                apply_state(args, &state)?;
+                shown = Some(brightness);
+            }
+        }
+        std::thread::sleep(interval);
+    }
+}
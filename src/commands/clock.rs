@@ -0,0 +1,34 @@
+use crate::commands::effect::print_frames;
+use crate::{open_controller, Args};
+use acer_rgb::clock::{Clock, ClockDisplay};
+use acer_rgb::{Brightness, ALL_ZONES};
+
+use color_eyre::eyre::Result;
+use std::time::Duration;
+
+// shows the time from `clock`, redrawing on every minute boundary unless `once`
+pub fn run(args: &Args, clock: &dyn Clock, display: &ClockDisplay, once: bool) -> Result<()> {
+    let brightness = Brightness::new(args.brightness)?;
+    let mut controller = open_controller(args)?;
+    let mut last = None;
+
+    loop {
+        let time = clock.now();
+        let frame = display.frame(time);
+        if last != Some(frame) {
+            let zones: Vec<_> = ALL_ZONES.into_iter().zip(frame).collect();
+            controller.apply_static(&zones, brightness)?;
+            last = Some(frame);
+        }
+
+        if args.dry_run {
+            println!("{}", time);
+            print_frames(&[(Duration::ZERO, frame)], brightness);
+            return Ok(());
+        }
+        if once {
+            return Ok(());
+        }
+        std::thread::sleep(time.until_next_minute());
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{open_controller, Args, EffectKind};
+use acer_rgb::effects::{Breathe, Effect, Engine, Fire, Frame, Gradient, Rainbow, Strobe};
+use acer_rgb::state::resolve_zone_colors;
+use acer_rgb::Brightness;
+
+use color_eyre::eyre::Result;
+use std::time::Duration;
+
+pub fn build(kind: &EffectKind) -> Result<Box<dyn Effect>> {
+    let effect: Box<dyn Effect> = match kind {
+        EffectKind::Breathe {
+            color,
+            zone_color,
+            period,
+        } => {
+            let mut colors: Frame = [*color; 4];
+            for (zone, color) in resolve_zone_colors(&[], zone_color, *color)? {
+                colors[zone.to_u8() as usize - 1] = color;
+            }
+            Box::new(Breathe {
+                colors,
+                period: *period,
+            })
+        }
+        EffectKind::Rainbow { period, direction } => Box::new(Rainbow {
+            period: *period,
+            direction: *direction,
+        }),
+        EffectKind::Strobe { color, on, off } => Box::new(Strobe {
+            color: *color,
+            on: *on,
+            off: *off,
+        }),
+        EffectKind::Fire { color, flicker } => Box::new(Fire::new(*color, *flicker)),
+        EffectKind::Gradient {
+            colors,
+            period,
+            direction,
+        } => Box::new(Gradient {
+            colors: colors.clone(),
+            period: *period,
+            direction: *direction,
+        }),
+    };
+    Ok(effect)
+}
+
+pub fn run(args: &Args, kind: &EffectKind, fps: u32, duration: Option<Duration>) -> Result<()> {
+    let mut effect = build(kind)?;
+    let brightness = Brightness::new(args.brightness)?;
+    let mut engine = Engine::new(open_controller(args)?, brightness, fps);
+
+    if args.dry_run {
+        // nothing to watch, so print what would be drawn instead
+        let duration = duration.unwrap_or(Duration::from_secs(1));
+        print_frames(&engine.frames(effect.as_mut(), duration), brightness);
+        return Ok(());
+    }
+
+    engine.run(effect.as_mut(), duration)?;
+    Ok(())
+}
+
+// one line per frame: the timestamp, a colored block per zone and the hex values
+pub fn print_frames(frames: &[(Duration, Frame)], brightness: Brightness) {
+    for (elapsed, frame) in frames {
+        let colors = frame.map(|color| color.scale(brightness));
+        let blocks: Vec<String> = colors
+            .iter()
+            .map(|c| format!("\x1b[48;2;{};{};{}m  \x1b[0m", c.red, c.green, c.blue))
+            .collect();
+        let hex: Vec<String> = colors
+            .iter()
+            .map(|c| format!("#{:02x}{:02x}{:02x}", c.red, c.green, c.blue))
+            .collect();
+        println!(
+            "{:>8.3}s  {}  {}",
+            elapsed.as_secs_f64(),
+            blocks.join(" "),
+            hex.join(" ")
+        );
+    }
+}
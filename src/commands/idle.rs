@@ -0,0 +1,81 @@
+use crate::{current_state, open_controller, Args};
+use acer_rgb::easing::Easing;
+use acer_rgb::idle::{IdleSource, IdleTimeouts};
+use acer_rgb::sensors::power::PowerSource;
+use acer_rgb::transition::crossfade;
+use acer_rgb::utils::format_duration;
+use acer_rgb::Brightness;
+
+use color_eyre::eyre::Result;
+use std::path::Path;
+use std::time::Duration;
+
+const FPS: u32 = 30;
+// how often the idle time is checked, and so how late a keypress is noticed
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// dims the lighting once `source` has been idle past the timeout for the
+// current power source, and puts it back on the next input. the dimmed state
+// isn't recorded, so restoring after boot still brings back the real one.
+pub fn run(
+    args: &Args,
+    source: &mut dyn IdleSource,
+    timeouts: IdleTimeouts,
+    dim_to: Brightness,
+    fade: Duration,
+    sysfs_root: &Path,
+) -> Result<()> {
+    loop {
+        let power = PowerSource::read(sysfs_root)?;
+        let idle = source.idle_time()?;
+        if idle < timeouts.get(power) {
+            std::thread::sleep(POLL_INTERVAL);
+            continue;
+        }
+        // nothing applied yet, so nothing to dim
+        let Some(previous) = current_state(args)? else {
+            std::thread::sleep(POLL_INTERVAL);
+            continue;
+        };
+
+        println!(
+            "Idle for {} on {}, dimming",
+            format_duration(idle.as_secs()),
+            power
+        );
+        let mut dimmed = previous.clone();
+        dimmed.brightness = dim_to;
+        // some firmware ignores the brightness for static colors
+        dimmed.scale_brightness = true;
+        let fade = match args.dry_run {
+            true => Duration::ZERO,
+            false => fade,
+        };
+        let mut controller = crossfade(
+            open_controller(args)?,
+            &previous,
+            &dimmed,
+            fade,
+            Easing::EaseOut,
+            FPS,
+        )?;
+        controller.apply(&dimmed)?;
+
+        // any input resets the idle time
+        let mut last = source.idle_time()?;
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let idle = source.idle_time()?;
+            if idle < last {
+                break;
+            }
+            last = idle;
+        }
+
+        println!("Input, restoring");
+        // what was applied may have changed while dimmed, so look again
+        if let Some(state) = current_state(args)? {
+            controller.apply(&state)?;
+        }
+    }
+}
@@ -0,0 +1,13 @@
+pub mod brightness;
+pub mod clock;
+pub mod effect;
+pub mod idle;
+pub mod monitor;
+pub mod notify;
+pub mod play;
+pub mod profile;
+pub mod progress;
+pub mod run;
+pub mod timer;
+pub mod transition;
+pub mod watch;
@@ -0,0 +1,291 @@
+use crate::commands::effect::print_frames;
+use crate::{open_controller, ActivityArgs, Args, GaugeArgs, MonitorKind};
+use acer_rgb::effects::{Breathe, Effect, Frame, FrameWriter};
+use acer_rgb::gauge::{spread, Activity, ColorScale, Gauge, GaugeStyle, Stop};
+use acer_rgb::sensors::battery::{Battery, BatteryStatus};
+use acer_rgb::sensors::cpu::CpuSampler;
+use acer_rgb::sensors::disk::DiskSampler;
+use acer_rgb::sensors::memory::{MemInfo, MemoryPressure};
+use acer_rgb::sensors::net::NetSampler;
+use acer_rgb::sensors::thermal::TempSensor;
+use acer_rgb::sensors::ByteRates;
+use acer_rgb::{Brightness, RGB};
+
+use color_eyre::eyre::Result;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const TEMP_STOPS: [&str; 3] = ["40=#00ff00", "65=#ffff00", "85=#ff0000"];
+const CPU_STOPS: [&str; 3] = ["0=#00ff00", "50=#ffff00", "100=#ff0000"];
+const BATTERY_STOPS: [&str; 3] = ["0=#ff0000", "40=#ffff00", "100=#00ff00"];
+const MEMORY_STOPS: [&str; 3] = ["50=#00ff00", "75=#ffff00", "90=#ff0000"];
+
+// rates in bytes per second. below the floor the zones stay dark, so
+// background chatter doesn't keep them lit
+const ACTIVITY_FLOOR: f64 = 1024.0;
+const NET_MAX: f64 = 100.0 * 1024.0 * 1024.0;
+const DISK_MAX: f64 = 1024.0 * 1024.0 * 1024.0;
+
+// time between frames while something is animating
+const FRAME_INTERVAL: Duration = Duration::from_millis(33);
+const WARNING_PERIOD: Duration = Duration::from_secs(1);
+
+pub fn run(
+    args: &Args,
+    monitor: &MonitorKind,
+    interval: Duration,
+    sysfs_root: &Path,
+    procfs_root: &Path,
+) -> Result<()> {
+    match monitor {
+        MonitorKind::Temp {
+            sensor,
+            list,
+            gauge,
+        } => {
+            if *list {
+                for sensor in TempSensor::discover(sysfs_root)? {
+                    println!("{:<16} {}", sensor.id, sensor.name);
+                }
+                return Ok(());
+            }
+
+            let sensor = TempSensor::find(sysfs_root, sensor.as_deref())?;
+            println!("Showing {} ({})", sensor.name, sensor.id);
+            let mut gauge = build_gauge(gauge, &TEMP_STOPS, GaugeStyle::Solid, 2.0)?;
+            watch(args, interval, |_| Ok(gauge.show(sensor.read()?)))
+        }
+        MonitorKind::Cpu { per_core, gauge } => {
+            let mut sampler = CpuSampler::new(procfs_root)?;
+            let mut gauge = build_gauge(gauge, &CPU_STOPS, GaugeStyle::Bar, 5.0)?;
+            // the first sample needs something to compare against
+            std::thread::sleep(interval.min(Duration::from_millis(250)));
+            watch(args, interval, |_| {
+                let load = sampler.sample()?;
+                Ok(match per_core {
+                    true => gauge.zones(spread(&load.cores)),
+                    false => gauge.show(load.total),
+                })
+            })
+        }
+        MonitorKind::Battery {
+            battery,
+            warn_below,
+            warning_color,
+            period,
+            gauge,
+        } => {
+            let battery = Battery::find(sysfs_root, battery.as_deref())?;
+            println!("Showing {}", battery.name);
+            let mut gauge = build_gauge(gauge, &BATTERY_STOPS, GaugeStyle::Bar, 0.0)?;
+
+            let mut reading = battery.read()?;
+            let mut last_read = Instant::now();
+            watch(args, FRAME_INTERVAL, |elapsed| {
+                if last_read.elapsed() >= interval {
+                    match battery.read() {
+                        Ok(read) => reading = read,
+                        Err(err) => eprintln!("{:#}, trying again", err),
+                    }
+                    last_read = Instant::now();
+                }
+                let frame = gauge.show(reading.capacity as f64);
+
+                let low =
+                    reading.status == BatteryStatus::Discharging && reading.capacity < *warn_below;
+                let frame = match reading.status {
+                    BatteryStatus::Charging => Breathe {
+                        colors: frame,
+                        period: *period,
+                    }
+                    .frame(elapsed),
+                    _ if low => Breathe {
+                        colors: [*warning_color; 4],
+                        period: WARNING_PERIOD,
+                    }
+                    .frame(elapsed),
+                    _ => frame,
+                };
+                Ok(frame)
+            })
+        }
+        MonitorKind::Memory {
+            warn_above,
+            warning_color,
+            gauge,
+        } => {
+            let meminfo = procfs_root.join("meminfo");
+            let psi = procfs_root.join("pressure/memory");
+            let psi = match psi.exists() {
+                true => Some(psi),
+                false => {
+                    println!(
+                        "No {}, the kernel needs CONFIG_PSI or psi=1 to warn about memory pressure",
+                        psi.display()
+                    );
+                    None
+                }
+            };
+            let mut gauge = build_gauge(gauge, &MEMORY_STOPS, GaugeStyle::Solid, 2.0)?;
+
+            let read = || -> Result<(f64, Option<MemoryPressure>)> {
+                let used = MemInfo::read(&meminfo)?.used_percent();
+                let pressure = psi.as_deref().map(MemoryPressure::read).transpose()?;
+                Ok((used, pressure))
+            };
+            let (mut used, mut pressure) = read()?;
+            let mut last_read = Instant::now();
+            watch(args, FRAME_INTERVAL, |elapsed| {
+                if last_read.elapsed() >= interval {
+                    match read() {
+                        Ok(reading) => (used, pressure) = reading,
+                        Err(err) => eprintln!("{:#}, trying again", err),
+                    }
+                    last_read = Instant::now();
+                }
+                let frame = gauge.show(used);
+                let stalled = pressure.is_some_and(|pressure| pressure.some.avg10 > *warn_above);
+                Ok(match stalled {
+                    true => Breathe {
+                        colors: [*warning_color; 4],
+                        period: WARNING_PERIOD,
+                    }
+                    .frame(elapsed),
+                    false => frame,
+                })
+            })
+        }
+        MonitorKind::Net {
+            iface,
+            rx_color,
+            tx_color,
+            activity,
+        } => {
+            let mut sampler = NetSampler::new(procfs_root, iface.as_deref())?;
+            println!("Showing {}", iface.as_deref().unwrap_or("all interfaces"));
+            show_activity(
+                args,
+                interval,
+                activity,
+                NET_MAX,
+                [*rx_color, *tx_color],
+                || Ok(sampler.sample()?),
+            )
+        }
+        MonitorKind::Disk {
+            block_device: device,
+            read_color,
+            write_color,
+            activity,
+        } => {
+            let mut sampler = DiskSampler::new(procfs_root, device)?;
+            println!("Showing {}", device);
+            show_activity(
+                args,
+                interval,
+                activity,
+                DISK_MAX,
+                [*read_color, *write_color],
+                || Ok(sampler.sample()?),
+            )
+        }
+    }
+}
+
+// shows the incoming rate on the first two zones and the outgoing one on the
+// last two, sampling every `interval` and fading in between. frames are
+// written at most `--fps` times a second.
+fn show_activity(
+    args: &Args,
+    interval: Duration,
+    activity: &ActivityArgs,
+    default_max: f64,
+    [incoming_color, outgoing_color]: [RGB; 2],
+    mut sample: impl FnMut() -> Result<ByteRates>,
+) -> Result<()> {
+    let max = activity.max.unwrap_or(default_max);
+    let mut incoming = Activity::new(ACTIVITY_FLOOR, max, activity.decay);
+    let mut outgoing = incoming.clone();
+
+    // the first sample needs something to compare against
+    std::thread::sleep(interval.min(Duration::from_millis(250)));
+    let mut rates = sample()?;
+    let mut last_read = Instant::now();
+    let mut last_frame = Duration::ZERO;
+    watch(
+        args,
+        Duration::from_secs(1) / activity.fps.max(1),
+        |elapsed| {
+            if last_read.elapsed() >= interval {
+                match sample() {
+                    Ok(sampled) => rates = sampled,
+                    Err(err) => eprintln!("{:#}, trying again", err),
+                }
+                last_read = Instant::now();
+            }
+            let since = elapsed.saturating_sub(last_frame);
+            last_frame = elapsed;
+
+            let incoming = incoming_color.dim(incoming.update(rates.incoming, since));
+            let outgoing = outgoing_color.dim(outgoing.update(rates.outgoing, since));
+            Ok([incoming, incoming, outgoing, outgoing])
+        },
+    )
+}
+
+// the gauge described by the flags, falling back to the given defaults
+fn build_gauge(
+    args: &GaugeArgs,
+    stops: &[&str],
+    style: GaugeStyle,
+    hysteresis: f64,
+) -> Result<Gauge> {
+    let stops = match args.stops.is_empty() {
+        true => stops
+            .iter()
+            .map(|stop| stop.parse())
+            .collect::<Result<Vec<Stop>, _>>()?,
+        false => args.stops.clone(),
+    };
+    let mut gauge = Gauge::new(
+        ColorScale::new(stops, !args.steps),
+        args.style.unwrap_or(style),
+        args.hysteresis.unwrap_or(hysteresis),
+    );
+    gauge.direction = args.direction;
+    Ok(gauge)
+}
+
+// asks `sample` for the frame at the elapsed time every `tick` until
+// interrupted, writing only the zones that changed. a dry run prints each new
+// frame instead. a failed sample keeps the last frame up until the next one.
+fn watch(
+    args: &Args,
+    tick: Duration,
+    mut sample: impl FnMut(Duration) -> Result<Frame>,
+) -> Result<()> {
+    let brightness = Brightness::new(args.brightness)?;
+    let mut writer = FrameWriter::new(open_controller(args)?, brightness);
+    let start = Instant::now();
+    let mut last = None;
+
+    loop {
+        let elapsed = start.elapsed();
+        let frame = match sample(elapsed) {
+            Ok(frame) => frame,
+            Err(err) => {
+                eprintln!("{:#}, trying again", err);
+                std::thread::sleep(tick);
+                continue;
+            }
+        };
+        if last != Some(frame) {
+            match args.dry_run {
+                true => print_frames(&[(elapsed, frame)], brightness),
+                false => writer.write(frame)?,
+            }
+            last = Some(frame);
+        }
+        std::thread::sleep(tick);
+    }
+}
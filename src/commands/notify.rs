@@ -0,0 +1,36 @@
+use crate::commands::effect::print_frames;
+use crate::{current_state, open_controller, overlay_controller, Args};
+use acer_rgb::effects::Engine;
+use acer_rgb::notify::{default_lock_path, show, Notification, NotifyLock};
+use acer_rgb::{Brightness, Zone, RGB};
+
+use color_eyre::eyre::Result;
+use std::time::Duration;
+
+const FPS: u32 = 30;
+
+pub fn run(args: &Args, color: RGB, zones: &[u8], times: u32, period: Duration) -> Result<()> {
+    // wait for notifications that are already showing
+    let _lock = NotifyLock::acquire(&default_lock_path())?;
+
+    let brightness = Brightness::new(args.brightness)?;
+    let zones = Zone::from_u8s(zones)?;
+    let mut notification = Notification::new(color.scale(brightness), zones, times, period);
+    if let Some(previous) = current_state(args)? {
+        notification = notification.over(&previous);
+    }
+
+    if args.dry_run {
+        let engine = Engine::new(open_controller(args)?, Brightness::MAX, FPS);
+        let duration = notification.duration();
+        print_frames(&engine.frames(&mut notification, duration), Brightness::MAX);
+        return Ok(());
+    }
+
+    let mut controller = show(overlay_controller(args)?, &mut notification, FPS)?;
+    // what was applied may have changed while blinking, so look again
+    if let Some(state) = current_state(args)? {
+        controller.apply(&state)?;
+    }
+    Ok(())
+}
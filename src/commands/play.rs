@@ -0,0 +1,34 @@
+use crate::commands::effect::print_frames;
+use crate::{open_controller, Args};
+use acer_rgb::effects::Engine;
+use acer_rgb::timeline::Timeline;
+use acer_rgb::utils::format_duration;
+use acer_rgb::Brightness;
+
+use color_eyre::eyre::Result;
+use std::path::Path;
+
+pub fn run(args: &Args, file: &Path, fps: u32) -> Result<()> {
+    let mut timeline = Timeline::load(file)?;
+    let brightness = Brightness::new(args.brightness)?;
+    let mut engine = Engine::new(open_controller(args)?, brightness, fps);
+
+    if args.dry_run {
+        // a timeline that loops forever is shown once
+        let duration = timeline.total_duration().unwrap_or(timeline.duration());
+        print_frames(&engine.frames(&mut timeline, duration), brightness);
+        return Ok(());
+    }
+
+    let total = timeline.total_duration();
+    match total {
+        Some(total) => println!(
+            "Playing {} for {}",
+            file.display(),
+            format_duration(total.as_secs())
+        ),
+        None => println!("Playing {} until interrupted", file.display()),
+    }
+    engine.run(&mut timeline, total)?;
+    Ok(())
+}
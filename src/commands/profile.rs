@@ -0,0 +1,58 @@
+use crate::{apply_state, current_state, load_profile, open_controller, state_from_args, Args};
+use acer_rgb::config::{RuleAction, SwitchRules};
+use acer_rgb::sensors::lid::LidState;
+use acer_rgb::sensors::power::PowerSource;
+use acer_rgb::{Brightness, LightingState, ALL_ZONES, RGB};
+
+use color_eyre::eyre::Result;
+use std::path::Path;
+use std::time::Duration;
+
+// checks the power source and lid every `interval` and acts on the rules when
+// what they call for changes, so lighting set by hand in between is kept.
+// profiles are recorded like any other change, turning off isn't, so
+// restoring after boot or opening the lid again brings the lighting back.
+pub fn run(
+    args: &Args,
+    rules: &SwitchRules,
+    interval: Duration,
+    sysfs_root: &Path,
+    procfs_root: &Path,
+) -> Result<()> {
+    let mut last: Option<Option<RuleAction>> = None;
+
+    loop {
+        let power = PowerSource::read(sysfs_root)?;
+        let lid = LidState::read(procfs_root)?;
+        let action = rules.action(power, lid).cloned();
+        if last.as_ref() != Some(&action) {
+            match &action {
+                Some(action) => println!("On {}, lid {}: {}", power, lid, action),
+                None => println!("On {}, lid {}: no rule", power, lid),
+            }
+            match &action {
+                Some(RuleAction::Profile(profile)) => {
+                    apply_state(args, &state_from_args(&load_profile(profile)?)?)?;
+                }
+                // black rather than brightness 0, which some firmware
+                // ignores for static colors
+                Some(RuleAction::Off) => {
+                    let off = LightingState::new_static(
+                        ALL_ZONES.iter().map(|&zone| (zone, RGB::BLACK)).collect(),
+                        Brightness::new(0)?,
+                    );
+                    open_controller(args)?.apply(&off)?;
+                }
+                // back on after being turned off
+                None if matches!(last, Some(Some(RuleAction::Off))) => {
+                    if let Some(state) = current_state(args)? {
+                        open_controller(args)?.apply(&state)?;
+                    }
+                }
+                None => {}
+            }
+            last = Some(action);
+        }
+        std::thread::sleep(interval);
+    }
+}
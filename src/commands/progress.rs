@@ -0,0 +1,42 @@
+use crate::commands::effect::print_frames;
+use crate::{open_controller, Args};
+use acer_rgb::effects::FrameWriter;
+use acer_rgb::gauge::bar;
+use acer_rgb::utils::parse_progress;
+use acer_rgb::{Brightness, Direction, RGB};
+
+use color_eyre::eyre::Result;
+use std::io::BufRead;
+use std::time::Instant;
+
+// one reading per line until stdin closes, e.g. from `pv -n`
+pub fn run(args: &Args, fill: RGB, empty: RGB, direction: Direction) -> Result<()> {
+    let brightness = Brightness::new(args.brightness)?;
+    let mut writer = FrameWriter::new(open_controller(args)?, brightness);
+    let start = Instant::now();
+    let mut last = None;
+
+    for line in std::io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fraction = match parse_progress(&line) {
+            Ok(fraction) => fraction,
+            Err(err) => {
+                eprintln!("{}", err);
+                continue;
+            }
+        };
+
+        let frame = bar(fraction, fill, empty, direction);
+        if last != Some(frame) {
+            match args.dry_run {
+                true => print_frames(&[(start.elapsed(), frame)], brightness),
+                false => writer.write(frame)?,
+            }
+            last = Some(frame);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use crate::{current_state, overlay_controller, Args};
+use acer_rgb::effects::{Effect, Frame, FrameWriter};
+use acer_rgb::notify::{default_lock_path, show, Notification, NotifyLock};
+use acer_rgb::{Brightness, ALL_ZONES, RGB};
+
+use color_eyre::eyre::{Result, WrapErr};
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Child, Command, ExitStatus};
+use std::time::{Duration, Instant};
+
+const FPS: u32 = 30;
+const SWEEP_PERIOD: Duration = Duration::from_millis(1500);
+
+// a light bouncing from one end of the keyboard to the other
+struct Sweep {
+    color: RGB,
+    // time to go there and back
+    period: Duration,
+}
+
+impl Effect for Sweep {
+    fn frame(&mut self, elapsed: Duration) -> Frame {
+        let phase = (elapsed.as_secs_f64() / self.period.as_secs_f64()).fract();
+        // 0 - 3 and back, as a zone position
+        let position = 3.0 * (1.0 - (2.0 * phase - 1.0).abs());
+        std::array::from_fn(|zone| {
+            self.color
+                .dim((1.0 - (zone as f64 - position).abs()).max(0.0))
+        })
+    }
+}
+
+pub struct Colors {
+    pub busy: RGB,
+    pub success: RGB,
+    pub failure: RGB,
+}
+
+// runs `command` and returns the exit code to pass on, 128 + the signal
+// number if it was killed and 127 if it couldn't be started, like a shell
+// would. the lighting is best effort:
+// once the command runs, failing to light the keyboard only warns, so the
+// exit code always gets through.
+pub fn run(
+    args: &Args,
+    command: &[String],
+    colors: &Colors,
+    times: u32,
+    period: Duration,
+) -> Result<i32> {
+    let brightness = Brightness::new(args.brightness)?;
+    let mut child = match Command::new(&command[0]).args(&command[1..]).spawn() {
+        Ok(child) => child,
+        Err(err) => {
+            eprintln!("Failed to run '{}': {}", command[0], err);
+            return Ok(127);
+        }
+    };
+
+    let mut busy = Sweep {
+        color: colors.busy,
+        period: SWEEP_PERIOD,
+    };
+    let mut writer = match overlay_controller(args) {
+        Ok(controller) => Some(FrameWriter::new(controller, brightness)),
+        Err(err) => {
+            eprintln!("Not animating: {}", err);
+            None
+        }
+    };
+    let status = wait_animated(&mut child, &mut writer, &mut busy)?;
+
+    if let Some(writer) = writer {
+        let color = match status.success() {
+            true => colors.success,
+            false => colors.failure,
+        };
+        if let Err(err) = flash(args, writer, color.scale(brightness), times, period) {
+            eprintln!("Failed to flash the result: {}", err);
+        }
+    }
+    Ok(exit_code(status))
+}
+
+// plays `busy` until `child` exits. a write that fails stops the animation
+// and drops `writer`, but the child is still waited for.
+fn wait_animated(
+    child: &mut Child,
+    writer: &mut Option<FrameWriter>,
+    busy: &mut Sweep,
+) -> Result<ExitStatus> {
+    let start = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(status),
+            Ok(None) => {}
+            // can't poll it, so stop animating and just block
+            Err(_) => return child.wait().wrap_err("Failed to wait for the command"),
+        }
+        if let Some(frame_writer) = writer {
+            if let Err(err) = frame_writer.write(busy.frame(start.elapsed())) {
+                eprintln!("Stopped the busy animation: {}", err);
+                *writer = None;
+            }
+        }
+        std::thread::sleep(Duration::from_secs(1) / FPS);
+    }
+}
+
+// flashes the result like `notify` does and puts the previous lighting back
+fn flash(args: &Args, writer: FrameWriter, color: RGB, times: u32, period: Duration) -> Result<()> {
+    let _lock = NotifyLock::acquire(&default_lock_path())?;
+    let mut notification = Notification::new(color, ALL_ZONES.to_vec(), times, period);
+    let previous = current_state(args)?;
+    if let Some(previous) = &previous {
+        notification = notification.over(previous);
+    }
+
+    let mut controller = show(writer.into_controller(), &mut notification, FPS)?;
+    if let Some(state) = current_state(args)? {
+        controller.apply(&state)?;
+    }
+    Ok(())
+}
+
+fn exit_code(status: ExitStatus) -> i32 {
+    status
+        .code()
+        .or_else(|| status.signal().map(|signal| 128 + signal))
+        .unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use acer_rgb::{Backend, DevicePayload, Error, KeyboardController};
+    use clap::Parser;
+
+    // a device node that went away
+    struct Unplugged;
+
+    impl Backend for Unplugged {
+        fn write(&mut self, _: &DevicePayload) -> acer_rgb::Result<()> {
+            Err(Error::WriteDevice {
+                path: "/dev/acer-gkbbl-0".to_string(),
+                source: std::io::Error::from(std::io::ErrorKind::NotFound),
+            })
+        }
+    }
+
+    fn exit_code_with_unplugged_keyboard(script: &str) -> i32 {
+        let mut child = Command::new("sh").args(["-c", script]).spawn().unwrap();
+        let controller = KeyboardController::with_backend(Unplugged);
+        let mut writer = Some(FrameWriter::new(controller, Brightness::MAX));
+        let mut busy = Sweep {
+            color: RGB::new(0, 0, 255),
+            period: SWEEP_PERIOD,
+        };
+        let status = wait_animated(&mut child, &mut writer, &mut busy).unwrap();
+        assert!(writer.is_none(), "a failed write should stop the animation");
+        exit_code(status)
+    }
+
+    #[test]
+    fn exit_code_survives_failing_writes() {
+        assert_eq!(exit_code_with_unplugged_keyboard("sleep 0.1; exit 3"), 3);
+        assert_eq!(exit_code_with_unplugged_keyboard("sleep 0.1"), 0);
+    }
+
+    #[test]
+    fn killed_commands_exit_like_a_shell() {
+        assert_eq!(
+            exit_code_with_unplugged_keyboard("sleep 0.1; kill -9 $$"),
+            137
+        );
+    }
+
+    #[test]
+    fn commands_that_dont_start_exit_with_127() {
+        let args = Args::parse_from(["acer-rgb", "--dry-run"]);
+        let colors = Colors {
+            busy: RGB::BLACK,
+            success: RGB::BLACK,
+            failure: RGB::BLACK,
+        };
+        let command = ["/nonexistent/command".to_string()];
+        let code = run(&args, &command, &colors, 1, Duration::ZERO).unwrap();
+        assert_eq!(code, 127);
+    }
+}
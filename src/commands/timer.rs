@@ -0,0 +1,138 @@
+use crate::{current_state, overlay_controller, socket_path, Args};
+use acer_rgb::daemon::{Client, Event};
+use acer_rgb::effects::FrameWriter;
+use acer_rgb::notify::{show, Notification};
+use acer_rgb::timer::{Phase, Schedule, TimerAction};
+use acer_rgb::utils::format_duration;
+use acer_rgb::{Brightness, Direction, ALL_ZONES, RGB};
+
+use color_eyre::eyre::{eyre, Result};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+const FPS: u32 = 30;
+const TICK: Duration = Duration::from_millis(100);
+const FLASH_TIMES: u32 = 3;
+const FLASH_PERIOD: Duration = Duration::from_millis(300);
+// how bright the zones stay while paused
+const PAUSED_LEVEL: f64 = 0.3;
+
+pub fn send(args: &Args, action: TimerAction) -> Result<()> {
+    let mut client = Client::connect(socket_path(args)).map_err(|_| {
+        eyre!(
+            "Pausing or resuming a timer needs a running daemon, start one with `acer-rgb daemon`"
+        )
+    })?;
+    client.timer(action)?;
+    Ok(())
+}
+
+pub fn run(args: &Args, schedule: &Schedule, end_color: RGB, direction: Direction) -> Result<()> {
+    if args.dry_run {
+        print_schedule(schedule, end_color);
+        return Ok(());
+    }
+
+    let brightness = Brightness::new(args.brightness)?;
+    let actions = listen(args);
+    let mut controller = overlay_controller(args)?;
+
+    for phase in schedule.phases() {
+        println!(
+            "{} for {}",
+            phase.kind,
+            format_duration(phase.duration.as_secs())
+        );
+        let mut writer = FrameWriter::new(controller, brightness);
+        count_down(&mut writer, phase, end_color, direction, actions.as_ref())?;
+
+        let mut flash = Notification::new(
+            end_color.scale(brightness),
+            ALL_ZONES.to_vec(),
+            FLASH_TIMES,
+            FLASH_PERIOD,
+        );
+        controller = show(writer.into_controller(), &mut flash, FPS)?;
+    }
+
+    // put the lighting from before the timer back
+    if let Some(state) = current_state(args)? {
+        controller.apply(&state)?;
+    }
+    Ok(())
+}
+
+// pauses and resumes sent through the daemon, if one is running
+fn listen(args: &Args) -> Option<Receiver<TimerAction>> {
+    let events = Client::connect(socket_path(args)).ok()?.subscribe().ok()?;
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for event in events {
+            match event {
+                Ok(Event::Timer(action)) => {
+                    if sender.send(action).is_err() {
+                        return;
+                    }
+                }
+                Ok(_) => {}
+                Err(_) => return,
+            }
+        }
+    });
+    Some(receiver)
+}
+
+fn count_down(
+    writer: &mut FrameWriter,
+    phase: &Phase,
+    end_color: RGB,
+    direction: Direction,
+    actions: Option<&Receiver<TimerAction>>,
+) -> Result<()> {
+    let mut elapsed = Duration::ZERO;
+    let mut paused = false;
+    let mut last = Instant::now();
+
+    while elapsed < phase.duration {
+        for action in actions.into_iter().flat_map(|actions| actions.try_iter()) {
+            paused = action == TimerAction::Pause;
+            println!("{}", if paused { "Paused" } else { "Resumed" });
+        }
+
+        let now = Instant::now();
+        if !paused {
+            elapsed += now - last;
+        }
+        last = now;
+
+        let frame = phase.frame(elapsed, end_color, direction);
+        match paused {
+            true => writer.write(frame.map(|color| color.dim(PAUSED_LEVEL)))?,
+            false => writer.write(frame)?,
+        }
+        std::thread::sleep(TICK);
+    }
+    Ok(())
+}
+
+fn print_schedule(schedule: &Schedule, end_color: RGB) {
+    println!("Schedule:");
+    let mut start = Duration::ZERO;
+    for phase in schedule.phases() {
+        println!(
+            "{:>8}  {:<5}  {:<7}  {}, shifting to {} from {}",
+            format_duration(start.as_secs()),
+            phase.kind,
+            format_duration(phase.duration.as_secs()),
+            phase.color,
+            end_color,
+            format_duration((start + phase.warn_at()).as_secs()),
+        );
+        start += phase.duration;
+    }
+    println!("{:>8}  done", format_duration(start.as_secs()));
+    println!(
+        "Every phase ends with {} flashes in {}.",
+        FLASH_TIMES, end_color
+    );
+}
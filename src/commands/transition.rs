@@ -0,0 +1,38 @@
+use crate::commands::effect::print_frames;
+use crate::{current_state, open_controller, overlay_controller, Args};
+use acer_rgb::effects::Engine;
+use acer_rgb::transition::{crossfade, Crossfade};
+use acer_rgb::{Brightness, LightingState};
+
+use color_eyre::eyre::Result;
+use std::time::Duration;
+
+const FPS: u32 = 30;
+
+// fades to `target` without applying it, that's left to the caller. with a
+// daemon running the frames go through it like the final state does
+pub fn run(args: &Args, target: &LightingState, duration: Duration) -> Result<()> {
+    let Some(current) = current_state(args)? else {
+        // nothing to fade from
+        return Ok(());
+    };
+
+    if args.dry_run {
+        let mut fade = Crossfade::between(&current, target, duration, args.easing);
+        let engine = Engine::new(open_controller(args)?, Brightness::MAX, FPS);
+        println!("Transition:");
+        print_frames(&engine.frames(&mut fade, duration), Brightness::MAX);
+        println!();
+        return Ok(());
+    }
+
+    crossfade(
+        overlay_controller(args)?,
+        &current,
+        target,
+        duration,
+        args.easing,
+        FPS,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use crate::{apply_state, load_profile, state_from_args, Args};
+use acer_rgb::rules::{Action, Reading, RuleSet};
+use acer_rgb::Brightness;
+
+use color_eyre::eyre::{Result, WrapErr};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+/// Where readings come from.
+pub enum Source {
+    Command(String),
+    File(PathBuf),
+}
+
+impl Source {
+    fn read(&self) -> Result<Reading> {
+        match self {
+            Source::Command(command) => {
+                let output = Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .output()
+                    .wrap_err_with(|| format!("Failed to run '{}'", command))?;
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                // commands that only report through their exit code
+                match stdout.trim().is_empty() {
+                    true => Ok(Reading::parse(
+                        &output.status.code().unwrap_or(-1).to_string(),
+                    )),
+                    false => Ok(Reading::parse(&stdout)),
+                }
+            }
+            Source::File(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+                Ok(Reading::parse(&contents))
+            }
+        }
+    }
+}
+
+pub fn run(
+    args: &Args,
+    source: &Source,
+    rules: &Path,
+    interval: Duration,
+    once: bool,
+) -> Result<()> {
+    let rules = RuleSet::load(rules)?;
+    let brightness = Brightness::new(args.brightness)?;
+    let mut last: Option<Action> = None;
+
+    loop {
+        // a file replaced mid-rename or a command that failed to start is
+        // tried again next time, only a single read gives up
+        let reading = match source.read() {
+            Ok(reading) => reading,
+            Err(err) if !once => {
+                eprintln!("{:#}, trying again", err);
+                std::thread::sleep(interval);
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        let action = rules.action(&reading).cloned();
+        if action != last {
+            let shown = reading.text.lines().next().unwrap_or_default();
+            match &action {
+                Some(action) => {
+                    println!("{}: {}", shown, action);
+                    let state = match action {
+                        Action::Profile(profile) => state_from_args(&load_profile(profile)?)?,
+                        action => action
+                            .state(brightness)
+                            .expect("only profiles have no state"),
+                    };
+                    apply_state(args, &state)?;
+                }
+                None => println!("{}: no rule matched", shown),
+            }
+            last = action;
+        }
+
+        if once {
+            return Ok(());
+        }
+        std::thread::sleep(interval);
+    }
+}
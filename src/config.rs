@@ -0,0 +1,172 @@
+use crate::error::{Error, Result};
+use crate::sensors::lid::LidState;
+use crate::sensors::power::PowerSource;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Settings read from `config.json` in the config directory.
+///
+/// Every field is optional, a missing file is the same as an empty one. The
+/// `rules` section is left to [`SwitchRules::load`], so a mistake in it only
+/// stops `auto-profile`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    /// Path of the dynamic character device.
+    pub device: Option<PathBuf>,
+    /// Path of the static character device.
+    pub static_device: Option<PathBuf>,
+}
+
+// the part of the config `SwitchRules::load` looks at
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct RulesSection {
+    rules: SwitchRules,
+}
+
+/// What to show when something about the machine changes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuleAction {
+    /// Load a saved profile, e.g. `{"profile": "dim"}`.
+    Profile(String),
+    /// Turn the lighting off, `"off"`.
+    Off,
+}
+
+impl std::fmt::Display for RuleAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleAction::Profile(name) => write!(f, "profile {}", name),
+            RuleAction::Off => write!(f, "off"),
+        }
+    }
+}
+
+/// The `rules` section of the config. A closed lid wins over the power
+/// source.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct SwitchRules {
+    /// When plugged in.
+    pub ac: Option<RuleAction>,
+    /// When running on battery.
+    pub battery: Option<RuleAction>,
+    /// While the lid is closed.
+    pub lid_closed: Option<RuleAction>,
+}
+
+impl SwitchRules {
+    /// Reads the `rules` section of the config at `path`, where unknown rules
+    /// are an error.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        read_json(path.as_ref()).map(|section: RulesSection| section.rules)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// The action for the machine's current power source and lid, if any
+    /// rule covers it.
+    pub fn action(&self, power: PowerSource, lid: LidState) -> Option<&RuleAction> {
+        let lid_closed = match lid {
+            LidState::Closed => self.lid_closed.as_ref(),
+            LidState::Open => None,
+        };
+        lid_closed.or(match power {
+            PowerSource::Ac => self.ac.as_ref(),
+            PowerSource::Battery => self.battery.as_ref(),
+        })
+    }
+}
+
+impl Config {
+    /// `~/.config/predator`, next to the saved profiles.
+    pub fn dir() -> PathBuf {
+        dirs::config_dir().unwrap_or_default().join("predator")
+    }
+
+    pub fn default_path() -> PathBuf {
+        Self::dir().join("config.json")
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        read_json(path.as_ref())
+    }
+}
+
+// a missing file reads as the default
+fn read_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(T::default()),
+        Err(source) => {
+            return Err(Error::ReadFile {
+                path: path.display().to_string(),
+                source,
+            })
+        }
+    };
+    serde_json::from_reader(file).map_err(|source| Error::ParseFile {
+        path: path.display().to_string(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_closed_lid_wins_over_the_power_source() {
+        let rules: SwitchRules = serde_json::from_str(
+            r#"{ "ac": { "profile": "bright" }, "battery": { "profile": "dim" }, "lid-closed": "off" }"#,
+        )
+        .unwrap();
+        let profile = |name: &str| RuleAction::Profile(name.to_string());
+
+        assert_eq!(
+            rules.action(PowerSource::Ac, LidState::Open),
+            Some(&profile("bright"))
+        );
+        assert_eq!(
+            rules.action(PowerSource::Battery, LidState::Open),
+            Some(&profile("dim"))
+        );
+        assert_eq!(
+            rules.action(PowerSource::Battery, LidState::Closed),
+            Some(&RuleAction::Off)
+        );
+
+        let battery_only = SwitchRules {
+            battery: Some(profile("dim")),
+            ..SwitchRules::default()
+        };
+        assert_eq!(battery_only.action(PowerSource::Ac, LidState::Closed), None);
+        assert!(SwitchRules::default().is_empty());
+    }
+
+    #[test]
+    fn unknown_rules_only_fail_the_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(
+            &path,
+            r#"{ "device": "/dev/acer-gkbbl-1", "rules": { "docked": "off" } }"#,
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.device, Some(PathBuf::from("/dev/acer-gkbbl-1")));
+        assert!(matches!(
+            SwitchRules::load(&path),
+            Err(Error::ParseFile { .. })
+        ));
+    }
+}
@@ -0,0 +1,520 @@
+//! A long running owner of the keyboard, controlled over a Unix socket.
+//!
+//! Every message is one line of JSON carrying the protocol `version` next to
+//! its `type`, e.g. `{"version":1,"type":"get-state"}`. Each request gets one
+//! response, except `subscribe`, which keeps the connection open and pushes a
+//! `changed` message after every apply and a `timer` message whenever a
+//! running timer is paused or resumed. A subscribed connection only gets
+//! pushes from then on, later requests on it are ignored.
+//!
+//! [`Client`] is also a [`Backend`], so short animations drawn over the
+//! applied state, like notifications, can go through the daemon too.
+
+use crate::backend::Backend;
+use crate::encoder::DevicePayload;
+use crate::error::{Error, Result};
+use crate::keyboard::KeyboardController;
+use crate::persist::AppliedState;
+use crate::state::LightingState;
+use crate::timer::TimerAction;
+
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+// a subscriber that takes longer than this to accept a message is dropped
+const SUBSCRIBER_TIMEOUT: Duration = Duration::from_secs(5);
+// how long a client waits for the daemon to take a request or answer it
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `$XDG_RUNTIME_DIR/acer-rgb.sock`.
+pub fn default_socket_path() -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("acer-rgb.sock")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Request {
+    /// Write a new state to the keyboard.
+    Apply { state: LightingState },
+    /// Ask for the last applied state.
+    GetState,
+    /// Get a `changed` message after every apply, until the connection closes.
+    Subscribe,
+    /// Pass a pause or resume on to the subscribed timers.
+    Timer { action: TimerAction },
+    /// Write payloads as they are, without recording them as the state.
+    Write { payloads: Vec<DevicePayload> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Response {
+    Applied { payloads: Vec<DevicePayload> },
+    State { state: Option<AppliedState> },
+    Subscribed,
+    Changed { state: AppliedState },
+    Timer { action: TimerAction },
+    Written,
+    Error { message: String },
+}
+
+/// Something pushed to subscribers.
+#[derive(Debug, Clone)]
+pub enum Event {
+    Changed(AppliedState),
+    Timer(TimerAction),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Message<T> {
+    version: u32,
+    #[serde(flatten)]
+    body: T,
+}
+
+fn send<T: Serialize>(stream: &mut UnixStream, body: T) -> std::io::Result<()> {
+    let message = Message {
+        version: PROTOCOL_VERSION,
+        body,
+    };
+    let mut line = serde_json::to_string(&message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())
+}
+
+/// Serves requests for a [`KeyboardController`], recording every apply in
+/// the state file like a direct run would.
+pub struct Daemon {
+    controller: Mutex<KeyboardController>,
+    state: Mutex<Option<AppliedState>>,
+    state_file: PathBuf,
+    /// One channel per subscriber, each drained by its own writer thread so
+    /// a client that stops reading can't hold up an apply.
+    subscribers: Mutex<Vec<Sender<Response>>>,
+}
+
+impl Daemon {
+    pub fn new(controller: KeyboardController, state_file: PathBuf) -> Result<Self> {
+        let state = AppliedState::load(&state_file)?;
+        Ok(Self {
+            controller: Mutex::new(controller),
+            state: Mutex::new(state),
+            state_file,
+            subscribers: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Listens on `socket` until the process is stopped.
+    ///
+    /// A socket left behind by a daemon that is no longer running is replaced.
+    pub fn run(self, socket: &Path) -> Result<()> {
+        let socket_err = |source| Error::Socket {
+            path: socket.display().to_string(),
+            source,
+        };
+
+        if UnixStream::connect(socket).is_ok() {
+            return Err(Error::Daemon(format!(
+                "another daemon is already listening on {}",
+                socket.display()
+            )));
+        }
+        if socket.exists() {
+            std::fs::remove_file(socket).map_err(socket_err)?;
+        }
+        if let Some(dir) = socket.parent() {
+            std::fs::create_dir_all(dir).map_err(socket_err)?;
+        }
+
+        let listener = UnixListener::bind(socket).map_err(socket_err)?;
+        std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o600))
+            .map_err(socket_err)?;
+
+        let daemon = Arc::new(self);
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("Failed to accept connection: {}", err);
+                    continue;
+                }
+            };
+            let daemon = Arc::clone(&daemon);
+            std::thread::spawn(move || {
+                if let Err(err) = daemon.handle(stream) {
+                    eprintln!("Connection closed: {}", err);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn handle(&self, stream: UnixStream) -> std::io::Result<()> {
+        let reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+
+        for line in reader.lines() {
+            let message = match serde_json::from_str::<Message<Request>>(&line?) {
+                Ok(message) => message,
+                Err(err) => {
+                    let message = format!("invalid request: {}", err);
+                    send(&mut writer, Response::Error { message })?;
+                    continue;
+                }
+            };
+
+            if message.version != PROTOCOL_VERSION {
+                let message = format!(
+                    "unsupported protocol version {}, expected {}",
+                    message.version, PROTOCOL_VERSION
+                );
+                send(&mut writer, Response::Error { message })?;
+                continue;
+            }
+
+            match message.body {
+                // the subscriber thread owns the connection from here, so
+                // responses can't end up between its pushes
+                Request::Subscribe => return self.subscribe(writer),
+                request => send(&mut writer, self.process(request))?,
+            }
+        }
+        Ok(())
+    }
+
+    // confirms the subscription and pushes broadcasts to `stream` from a
+    // thread of its own, until a write fails or times out
+    fn subscribe(&self, mut stream: UnixStream) -> std::io::Result<()> {
+        stream.set_write_timeout(Some(SUBSCRIBER_TIMEOUT))?;
+        let (sender, receiver) = mpsc::channel();
+        // queued before registering, so it goes out ahead of every push
+        let _ = sender.send(Response::Subscribed);
+        std::thread::spawn(move || {
+            for response in receiver {
+                if send(&mut stream, response).is_err() {
+                    break;
+                }
+            }
+        });
+        self.subscribers.lock().unwrap().push(sender);
+        Ok(())
+    }
+
+    fn process(&self, request: Request) -> Response {
+        match request {
+            Request::Apply { state } => match self.apply(state) {
+                Ok(payloads) => Response::Applied { payloads },
+                Err(err) => Response::Error {
+                    message: err.to_string(),
+                },
+            },
+            Request::GetState => Response::State {
+                state: self.state.lock().unwrap().clone(),
+            },
+            Request::Subscribe => Response::Subscribed,
+            Request::Timer { action } => {
+                self.broadcast(Response::Timer { action });
+                Response::Timer { action }
+            }
+            Request::Write { payloads } => match self.controller.lock().unwrap().write(&payloads) {
+                Ok(()) => Response::Written,
+                Err(err) => Response::Error {
+                    message: err.to_string(),
+                },
+            },
+        }
+    }
+
+    /// Applies `state` and tells every subscriber about it.
+    pub fn apply(&self, state: LightingState) -> Result<Vec<DevicePayload>> {
+        let payloads = self.controller.lock().unwrap().apply(&state)?;
+
+        let applied = AppliedState::new(state, payloads.clone());
+        if let Err(err) = applied.save(&self.state_file) {
+            eprintln!("{}", err);
+        }
+        *self.state.lock().unwrap() = Some(applied.clone());
+
+        self.broadcast(Response::Changed { state: applied });
+        Ok(payloads)
+    }
+
+    fn broadcast(&self, response: Response) {
+        // drop subscribers whose writer thread gave up
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(response.clone()).is_ok());
+    }
+}
+
+/// A connection to a running [`Daemon`].
+pub struct Client {
+    path: PathBuf,
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl Client {
+    /// Fails if no daemon is listening on `socket`.
+    pub fn connect(socket: impl AsRef<Path>) -> Result<Self> {
+        let path = socket.as_ref().to_path_buf();
+        let socket_err = |source| Error::Socket {
+            path: path.display().to_string(),
+            source,
+        };
+
+        let writer = UnixStream::connect(&path).map_err(socket_err)?;
+        writer
+            .set_read_timeout(Some(CLIENT_TIMEOUT))
+            .map_err(socket_err)?;
+        writer
+            .set_write_timeout(Some(CLIENT_TIMEOUT))
+            .map_err(socket_err)?;
+        let reader = BufReader::new(writer.try_clone().map_err(socket_err)?);
+        Ok(Self {
+            path,
+            reader,
+            writer,
+        })
+    }
+
+    fn socket_err(&self, source: std::io::Error) -> Error {
+        Error::Socket {
+            path: self.path.display().to_string(),
+            source,
+        }
+    }
+
+    /// Reads the next message, failing on `error` responses.
+    pub fn receive(&mut self) -> Result<Response> {
+        let mut line = String::new();
+        let read = self
+            .reader
+            .read_line(&mut line)
+            .map_err(|err| self.socket_err(err))?;
+        if read == 0 {
+            return Err(Error::Daemon("connection closed".to_string()));
+        }
+
+        let message: Message<Response> = serde_json::from_str(&line)
+            .map_err(|err| Error::Daemon(format!("invalid response: {}", err)))?;
+        match message.body {
+            Response::Error { message } => Err(Error::Daemon(message)),
+            response => Ok(response),
+        }
+    }
+
+    pub fn request(&mut self, request: Request) -> Result<Response> {
+        send(&mut self.writer, request).map_err(|err| self.socket_err(err))?;
+        self.receive()
+    }
+
+    pub fn apply(&mut self, state: LightingState) -> Result<Vec<DevicePayload>> {
+        match self.request(Request::Apply { state })? {
+            Response::Applied { payloads } => Ok(payloads),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    pub fn get_state(&mut self) -> Result<Option<AppliedState>> {
+        match self.request(Request::GetState)? {
+            Response::State { state } => Ok(state),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Tells running timers to pause or resume.
+    pub fn timer(&mut self, action: TimerAction) -> Result<()> {
+        match self.request(Request::Timer { action })? {
+            Response::Timer { .. } => Ok(()),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Turns the connection into a stream of events, which waits as long as
+    /// it takes for the next one.
+    pub fn subscribe(mut self) -> Result<impl Iterator<Item = Result<Event>>> {
+        match self.request(Request::Subscribe)? {
+            Response::Subscribed => {}
+            response => return Err(unexpected(response)),
+        }
+        self.reader
+            .get_ref()
+            .set_read_timeout(None)
+            .map_err(|err| self.socket_err(err))?;
+        // stops after the first error, e.g. when the daemon exits
+        let mut closed = false;
+        Ok(std::iter::from_fn(move || {
+            if closed {
+                return None;
+            }
+            let next = match self.receive() {
+                Ok(Response::Changed { state }) => Ok(Event::Changed(state)),
+                Ok(Response::Timer { action }) => Ok(Event::Timer(action)),
+                Ok(response) => Err(unexpected(response)),
+                Err(err) => Err(err),
+            };
+            closed = next.is_err();
+            Some(next)
+        }))
+    }
+}
+
+impl Backend for Client {
+    fn write(&mut self, payload: &DevicePayload) -> Result<()> {
+        let payloads = vec![payload.clone()];
+        match self.request(Request::Write { payloads })? {
+            Response::Written => Ok(()),
+            response => Err(unexpected(response)),
+        }
+    }
+}
+
+fn unexpected(response: Response) -> Error {
+    Error::Daemon(format!("unexpected response: {:?}", response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::Recorder;
+    use crate::types::{Brightness, Zone, RGB};
+    use std::time::Instant;
+
+    // a daemon listening in `dir`, writing to a recorder
+    fn serve(dir: &Path) -> (PathBuf, Recorder) {
+        let recorder = Recorder::new();
+        let socket = dir.join("acer-rgb.sock");
+        let daemon = Daemon::new(
+            KeyboardController::with_backend(recorder.clone()),
+            dir.join("state.json"),
+        )
+        .unwrap();
+        let path = socket.clone();
+        std::thread::spawn(move || daemon.run(&path));
+        while Client::connect(&socket).is_err() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        (socket, recorder)
+    }
+
+    #[test]
+    fn stalled_subscriber_does_not_block_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        let daemon = Arc::new(
+            Daemon::new(
+                KeyboardController::with_backend(Recorder::new()),
+                dir.path().join("state.json"),
+            )
+            .unwrap(),
+        );
+
+        // subscribes, then never reads again
+        let (mut client, server) = UnixStream::pair().unwrap();
+        let handler = Arc::clone(&daemon);
+        std::thread::spawn(move || handler.handle(server));
+        send(&mut client, Request::Subscribe).unwrap();
+
+        let state = LightingState::new_static(
+            vec![(Zone::new(1).unwrap(), RGB::new(255, 0, 0))],
+            Brightness::MAX,
+        );
+        let start = Instant::now();
+        // far more than fits in the socket buffer
+        for _ in 0..2000 {
+            daemon.apply(state.clone()).unwrap();
+        }
+        assert!(start.elapsed() < SUBSCRIBER_TIMEOUT);
+    }
+
+    #[test]
+    fn writes_through_a_client_are_not_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let (socket, recorder) = serve(dir.path());
+
+        let client = Client::connect(&socket).unwrap();
+        let mut controller = KeyboardController::with_backend(client);
+        let red = RGB::new(255, 0, 0);
+        let payloads = controller
+            .apply_static(&[(Zone::new(2).unwrap(), red)], Brightness::MAX)
+            .unwrap();
+        assert_eq!(recorder.payloads(), payloads);
+        assert!(Client::connect(&socket)
+            .unwrap()
+            .get_state()
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn clients_apply_read_and_follow_the_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let (socket, recorder) = serve(dir.path());
+        let mut events = Client::connect(&socket).unwrap().subscribe().unwrap();
+
+        let state = LightingState::new_static(
+            vec![(Zone::new(4).unwrap(), RGB::new(0, 255, 0))],
+            Brightness::MAX,
+        );
+        let mut client = Client::connect(&socket).unwrap();
+        let payloads = client.apply(state.clone()).unwrap();
+        assert_eq!(recorder.payloads(), payloads);
+
+        let applied = client.get_state().unwrap().unwrap();
+        assert_eq!(applied.state, state);
+        match events.next() {
+            Some(Ok(Event::Changed(changed))) => assert_eq!(changed.state, state),
+            event => panic!("expected a change, got {:?}", event),
+        }
+    }
+
+    #[test]
+    fn requests_after_subscribing_are_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let (socket, _) = serve(dir.path());
+
+        let mut subscriber = Client::connect(&socket).unwrap();
+        assert!(matches!(
+            subscriber.request(Request::Subscribe).unwrap(),
+            Response::Subscribed
+        ));
+        send(&mut subscriber.writer, Request::GetState).unwrap();
+        Client::connect(&socket)
+            .unwrap()
+            .timer(TimerAction::Pause)
+            .unwrap();
+        assert!(matches!(
+            subscriber.receive().unwrap(),
+            Response::Timer {
+                action: TimerAction::Pause
+            }
+        ));
+    }
+
+    #[test]
+    fn other_protocol_versions_get_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let (socket, _) = serve(dir.path());
+
+        let mut stream = UnixStream::connect(&socket).unwrap();
+        stream
+            .write_all(b"{\"version\":2,\"type\":\"get-state\"}\n")
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        let message: Message<Response> = serde_json::from_str(&line).unwrap();
+        assert_eq!(message.version, PROTOCOL_VERSION);
+        assert!(matches!(message.body, Response::Error { .. }));
+    }
+}
@@ -0,0 +1,37 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// How an animation moves between two values over time.
+#[derive(Debug, Clone, Copy, Default, ValueEnum, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    Cubic,
+    /// Jumps to the end value at the end.
+    Step,
+}
+
+impl Easing {
+    /// Maps progress `t` (0.0 - 1.0) to eased progress.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => (1.0 - (std::f64::consts::PI * t).cos()) / 2.0,
+            Easing::Cubic => match t < 0.5 {
+                true => 4.0 * t * t * t,
+                false => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+            },
+            Easing::Step => match t < 1.0 {
+                true => 0.0,
+                false => 1.0,
+            },
+        }
+    }
+}
@@ -0,0 +1,333 @@
+//! Software animations, drawn by rewriting the static zone colors many times
+//! a second.
+
+use crate::encoder::{encode_static, Device};
+use crate::error::Result;
+use crate::keyboard::KeyboardController;
+use crate::types::{Brightness, Direction, ALL_ZONES, RGB};
+
+use std::f64::consts::PI;
+use std::time::{Duration, Instant};
+
+/// The color of each zone, left to right.
+pub type Frame = [RGB; 4];
+
+/// Something that can be drawn on the four zones.
+pub trait Effect {
+    /// The frame to show `elapsed` after the effect started.
+    fn frame(&mut self, elapsed: Duration) -> Frame;
+}
+
+// how far into the current period `elapsed` is, 0.0 - 1.0
+fn phase(elapsed: Duration, period: Duration) -> f64 {
+    match period.is_zero() {
+        true => 0.0,
+        false => (elapsed.as_secs_f64() / period.as_secs_f64()).fract(),
+    }
+}
+
+// zone index as seen from where the animation starts
+fn position(zone: usize, direction: Direction) -> usize {
+    match direction {
+        Direction::LeftToRight => zone,
+        Direction::RightToLeft => 3 - zone,
+    }
+}
+
+/// Fades each zone's own color in and out.
+#[derive(Debug, Clone)]
+pub struct Breathe {
+    pub colors: Frame,
+    pub period: Duration,
+}
+
+impl Effect for Breathe {
+    fn frame(&mut self, elapsed: Duration) -> Frame {
+        let level = (1.0 - (2.0 * PI * phase(elapsed, self.period)).cos()) / 2.0;
+        self.colors.map(|color| color.dim(level))
+    }
+}
+
+/// Cycles through every hue, a quarter turn apart on neighbouring zones.
+#[derive(Debug, Clone)]
+pub struct Rainbow {
+    pub period: Duration,
+    pub direction: Direction,
+}
+
+impl Effect for Rainbow {
+    fn frame(&mut self, elapsed: Duration) -> Frame {
+        let hue = 360.0 * phase(elapsed, self.period);
+        std::array::from_fn(|zone| {
+            let offset = 90.0 * position(zone, self.direction) as f64;
+            RGB::from_hsv(hue - offset, 1.0, 1.0)
+        })
+    }
+}
+
+/// Flashes a color on and off.
+#[derive(Debug, Clone)]
+pub struct Strobe {
+    pub color: RGB,
+    pub on: Duration,
+    pub off: Duration,
+}
+
+impl Effect for Strobe {
+    fn frame(&mut self, elapsed: Duration) -> Frame {
+        let on = phase(elapsed, self.on + self.off) * (self.on + self.off).as_secs_f64();
+        match on < self.on.as_secs_f64() {
+            true => [self.color; 4],
+            false => [RGB::BLACK; 4],
+        }
+    }
+}
+
+/// Flickers like a fire or candle around a base color.
+#[derive(Debug, Clone)]
+pub struct Fire {
+    pub color: RGB,
+    /// How far the brightness may drop, 0.0 - 1.0.
+    pub flicker: f64,
+    levels: [f64; 4],
+    seed: u64,
+}
+
+impl Fire {
+    pub fn new(color: RGB, flicker: f64) -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0x2545_f491_4f6c_dd1d, |t| t.as_nanos() as u64)
+            | 1;
+        Self {
+            color,
+            flicker: flicker.clamp(0.0, 1.0),
+            levels: [1.0; 4],
+            seed,
+        }
+    }
+
+    // xorshift, plenty for flickering
+    fn random(&mut self) -> f64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        (self.seed >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl Effect for Fire {
+    fn frame(&mut self, _elapsed: Duration) -> Frame {
+        for zone in 0..4 {
+            // drift towards a new random level so it doesn't look like noise
+            let target = 1.0 - self.flicker * self.random();
+            self.levels[zone] += (target - self.levels[zone]) * 0.3;
+        }
+        let ember = RGB::new(self.color.red, self.color.green / 3, 0);
+        self.levels
+            .map(|level| ember.lerp(self.color, level).dim(level))
+    }
+}
+
+/// Scrolls a looping gradient through the given colors across the zones.
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    pub colors: Vec<RGB>,
+    pub period: Duration,
+    pub direction: Direction,
+}
+
+impl Gradient {
+    /// The color at `t` (0.0 - 1.0) along the gradient, wrapping around to
+    /// the first color at the end.
+    pub fn sample(&self, t: f64) -> RGB {
+        match self.colors.len() {
+            0 => RGB::BLACK,
+            1 => self.colors[0],
+            len => {
+                let position = t.rem_euclid(1.0) * len as f64;
+                let index = position as usize % len;
+                self.colors[index].lerp(self.colors[(index + 1) % len], position.fract())
+            }
+        }
+    }
+}
+
+impl Effect for Gradient {
+    fn frame(&mut self, elapsed: Duration) -> Frame {
+        let shift = phase(elapsed, self.period);
+        std::array::from_fn(|zone| {
+            let zone = position(zone, self.direction) as f64;
+            self.sample(zone / 4.0 - shift)
+        })
+    }
+}
+
+/// Writes frames to the static device, only sending the zones that changed.
+pub struct FrameWriter {
+    controller: KeyboardController,
+    brightness: Brightness,
+    last: Option<Frame>,
+}
+
+impl FrameWriter {
+    pub fn new(controller: KeyboardController, brightness: Brightness) -> Self {
+        Self {
+            controller,
+            brightness,
+            last: None,
+        }
+    }
+
+    /// The first frame switches the keyboard to static mode, later ones only
+    /// update zones whose color changed.
+    pub fn write(&mut self, frame: Frame) -> Result<()> {
+        let zones: Vec<_> = ALL_ZONES
+            .iter()
+            .zip(frame)
+            .enumerate()
+            .filter(|(i, (_, color))| self.last.map_or(true, |last| last[*i] != *color))
+            .map(|(_, (&zone, color))| (zone, color))
+            .collect();
+
+        match self.last {
+            None => {
+                self.controller.apply_static(&zones, self.brightness)?;
+            }
+            Some(_) => {
+                let payloads: Vec<_> = encode_static(&zones, self.brightness)
+                    .into_iter()
+                    .filter(|payload| payload.device == Device::Static)
+                    .collect();
+                self.controller.write(&payloads)?;
+            }
+        }
+        self.last = Some(frame);
+        Ok(())
+    }
+
+    /// Forgets the last frame, so the next one is written in full.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    pub fn into_controller(self) -> KeyboardController {
+        self.controller
+    }
+}
+
+/// Plays effects at a fixed frame rate.
+pub struct Engine {
+    writer: FrameWriter,
+    fps: u32,
+}
+
+impl Engine {
+    pub fn new(controller: KeyboardController, brightness: Brightness, fps: u32) -> Self {
+        Self {
+            writer: FrameWriter::new(controller, brightness),
+            fps: fps.max(1),
+        }
+    }
+
+    pub fn frame_interval(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.fps as f64)
+    }
+
+    /// Draws `effect` until `duration` has passed, ending on the frame at
+    /// exactly `duration`, or forever without one.
+    pub fn run(&mut self, effect: &mut dyn Effect, duration: Option<Duration>) -> Result<()> {
+        let start = Instant::now();
+        let interval = self.frame_interval();
+        let mut next = start;
+
+        loop {
+            let elapsed = start.elapsed();
+            if let Some(duration) = duration.filter(|&duration| elapsed >= duration) {
+                return self.writer.write(effect.frame(duration));
+            }
+            self.writer.write(effect.frame(elapsed))?;
+
+            next += interval;
+            match next.checked_duration_since(Instant::now()) {
+                Some(wait) => std::thread::sleep(wait),
+                // running behind, don't try to catch up
+                None => next = Instant::now(),
+            }
+        }
+    }
+
+    /// The frames `run` would draw over `duration`, with their timestamps,
+    /// computed without waiting or writing anything.
+    pub fn frames(&self, effect: &mut dyn Effect, duration: Duration) -> Vec<(Duration, Frame)> {
+        let interval = self.frame_interval();
+        let count = (duration.as_secs_f64() / interval.as_secs_f64()).ceil() as u32;
+        (0..count)
+            .map(|i| interval * i)
+            .chain(std::iter::once(duration))
+            .map(|elapsed| (elapsed, effect.frame(elapsed)))
+            .collect()
+    }
+
+    pub fn into_controller(self) -> KeyboardController {
+        self.writer.into_controller()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::Recorder;
+
+    const RED: RGB = RGB::new(255, 0, 0);
+    const BLUE: RGB = RGB::new(0, 0, 255);
+
+    #[test]
+    fn frame_writer_only_resends_changed_zones() {
+        let recorder = Recorder::new();
+        let mut writer = FrameWriter::new(
+            KeyboardController::with_backend(recorder.clone()),
+            Brightness::MAX,
+        );
+
+        writer.write([RED; 4]).unwrap();
+        // four zones and the switch to static mode
+        assert_eq!(recorder.payloads().len(), 5);
+
+        recorder.clear();
+        writer.write([RED, BLUE, RED, RED]).unwrap();
+        let payloads = recorder.payloads();
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0].device, Device::Static);
+        assert_eq!(payloads[0].payload, vec![0x02, 0, 0, 255]);
+
+        recorder.clear();
+        writer.write([RED, BLUE, RED, RED]).unwrap();
+        assert!(recorder.payloads().is_empty());
+    }
+
+    #[test]
+    fn strobe_flashes_for_its_on_time() {
+        let mut strobe = Strobe {
+            color: RED,
+            on: Duration::from_millis(100),
+            off: Duration::from_millis(300),
+        };
+        assert_eq!(strobe.frame(Duration::from_millis(50)), [RED; 4]);
+        assert_eq!(strobe.frame(Duration::from_millis(250)), [RGB::BLACK; 4]);
+        assert_eq!(strobe.frame(Duration::from_millis(450)), [RED; 4]);
+    }
+
+    #[test]
+    fn gradient_wraps_around_to_the_first_color() {
+        let gradient = Gradient {
+            colors: vec![RED, BLUE],
+            period: Duration::from_secs(1),
+            direction: Direction::LeftToRight,
+        };
+        assert_eq!(gradient.sample(0.0), RED);
+        assert_eq!(gradient.sample(0.5), BLUE);
+        assert_eq!(gradient.sample(0.75), RGB::new(128, 0, 128));
+        assert_eq!(gradient.sample(1.25), RGB::new(128, 0, 128));
+    }
+}
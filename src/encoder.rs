@@ -0,0 +1,114 @@
+use crate::state::LightingState;
+use crate::types::{Brightness, Direction, LightingMode, Speed, Zone, RGB};
+
+use serde::{Deserialize, Serialize};
+
+pub const PAYLOAD_SIZE: usize = 16;
+pub const PAYLOAD_SIZE_STATIC: usize = 4;
+pub const CHARACTER_DEVICE: &str = "/dev/acer-gkbbl-0";
+pub const CHARACTER_DEVICE_STATIC: &str = "/dev/acer-gkbbl-static-0";
+
+/// The character device a payload is meant for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Device {
+    /// `/dev/acer-gkbbl-0`, takes 16 byte mode payloads.
+    Dynamic,
+    /// `/dev/acer-gkbbl-static-0`, takes 4 byte per-zone color payloads.
+    Static,
+}
+
+impl Device {
+    pub fn default_path(self) -> &'static str {
+        match self {
+            Device::Dynamic => CHARACTER_DEVICE,
+            Device::Static => CHARACTER_DEVICE_STATIC,
+        }
+    }
+}
+
+/// Raw bytes to be written to one of the devices.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DevicePayload {
+    pub device: Device,
+    pub payload: Vec<u8>,
+}
+
+impl std::fmt::Display for DevicePayload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Device: {}\nPayload: {:02X?}",
+            self.device.default_path(),
+            self.payload
+        )
+    }
+}
+
+/// Builds the payloads for static mode: one color payload per zone, followed
+/// by the dynamic payload that switches the keyboard into static mode.
+pub fn encode_static(zone_colors: &[(Zone, RGB)], brightness: Brightness) -> Vec<DevicePayload> {
+    let mut payloads = Vec::new();
+
+    for &(zone, color) in zone_colors {
+        let mut static_payload = [0u8; PAYLOAD_SIZE_STATIC];
+        static_payload[0] = zone.to_mask();
+        static_payload[1..4].copy_from_slice(&color.to_bytes());
+
+        payloads.push(DevicePayload {
+            device: Device::Static,
+            payload: static_payload.to_vec(),
+        });
+    }
+
+    let mut dynamic_payload = [0u8; PAYLOAD_SIZE];
+    dynamic_payload[2] = brightness.get();
+    dynamic_payload[9] = 1;
+
+    payloads.push(DevicePayload {
+        device: Device::Dynamic,
+        payload: dynamic_payload.to_vec(),
+    });
+    payloads
+}
+
+/// Builds the payload for one of the firmware animations.
+pub fn encode_dynamic(
+    mode: LightingMode,
+    speed: Speed,
+    brightness: Brightness,
+    direction: Direction,
+    color: RGB,
+) -> DevicePayload {
+    let mut payload = [0u8; PAYLOAD_SIZE];
+    payload[0] = mode as u8;
+    payload[1] = speed.get();
+    payload[2] = brightness.get();
+    payload[3] = if matches!(mode, LightingMode::Wave) {
+        8
+    } else {
+        0
+    };
+    payload[4] = direction as u8;
+    payload[5..8].copy_from_slice(&color.to_bytes());
+    payload[9] = 1;
+
+    DevicePayload {
+        device: Device::Dynamic,
+        payload: payload.to_vec(),
+    }
+}
+
+/// Builds every payload needed to show `state`.
+pub fn encode(state: &LightingState) -> Vec<DevicePayload> {
+    match state.mode {
+        LightingMode::Static => encode_static(&state.static_zones(), state.brightness),
+        _ => vec![encode_dynamic(
+            state.mode,
+            state.speed,
+            state.brightness,
+            state.direction,
+            state.color,
+        )],
+    }
+}
@@ -0,0 +1,80 @@
+use std::io;
+use thiserror::Error;
+
+/// Errors returned by the `acer_rgb` library.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Zone must be between 1 and 4, got {0}")]
+    InvalidZone(u8),
+
+    #[error("Speed should be between 0 and 9, got {0}")]
+    InvalidSpeed(u8),
+
+    #[error("Brightness must be between 0 and 100, got {0}")]
+    InvalidBrightness(u8),
+
+    #[error("Invalid color '{input}': {reason}")]
+    InvalidColor { input: String, reason: String },
+
+    #[error("Invalid color stop '{input}': {reason}")]
+    InvalidStop { input: String, reason: String },
+
+    #[error("Invalid curve point '{input}': {reason}")]
+    InvalidCurvePoint { input: String, reason: String },
+
+    #[error("Device {path} from {origin} doesn't exist")]
+    DeviceNotFound { path: String, origin: String },
+
+    #[error("Failed to open device {path}")]
+    OpenDevice {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("Failed to write payload to {path}")]
+    WriteDevice {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("Failed to read {path}")]
+    ReadFile {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("Failed to write {path}")]
+    WriteFile {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("Failed to parse {path}")]
+    ParseFile {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("Failed to talk to the daemon at {path}")]
+    Socket {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("Daemon error: {0}")]
+    Daemon(String),
+
+    #[error("Invalid reading in {path}: {reason}")]
+    InvalidReading { path: String, reason: String },
+
+    #[error("Sensor error: {0}")]
+    Sensor(String),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,342 @@
+//! Turning readings like a temperature or a load into zone colors.
+
+use crate::effects::Frame;
+use crate::error::{Error, Result};
+use crate::types::{Direction, RGB};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// A color pinned to a reading, e.g. red at 85°C.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Stop {
+    pub value: f64,
+    pub color: RGB,
+}
+
+/// Parses `value=color`, e.g. `85=#ff0000`.
+impl FromStr for Stop {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: &str| Error::InvalidStop {
+            input: s.to_string(),
+            reason: reason.to_string(),
+        };
+        let (value, color) = s
+            .split_once('=')
+            .ok_or_else(|| invalid("expected value=color (e.g., 85=#ff0000)"))?;
+        let value: f64 = value
+            .trim()
+            .parse()
+            .map_err(|_| invalid("the value must be a number"))?;
+        if !value.is_finite() {
+            return Err(invalid("the value must be a number"));
+        }
+        Ok(Self {
+            value,
+            color: color.trim().parse()?,
+        })
+    }
+}
+
+/// Colors along a range of readings, e.g. green at 40°C through yellow to red
+/// at 85°C. The stops double as the thresholds a [`Gauge`] holds on to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColorScale {
+    stops: Vec<Stop>,
+    /// Blend between neighbouring stops instead of jumping at each one.
+    pub blend: bool,
+}
+
+impl ColorScale {
+    pub fn new(mut stops: Vec<Stop>, blend: bool) -> Self {
+        stops.sort_by(|a, b| a.value.total_cmp(&b.value));
+        Self { stops, blend }
+    }
+
+    /// The stops, lowest value first.
+    pub fn stops(&self) -> &[Stop] {
+        &self.stops
+    }
+
+    /// The color for `value`. Readings outside the scale get the color of the
+    /// nearest end.
+    pub fn color(&self, value: f64) -> RGB {
+        let Some(first) = self.stops.first() else {
+            return RGB::BLACK;
+        };
+        // the last stop at or below the value
+        let index = match self.stops.iter().rposition(|stop| stop.value <= value) {
+            Some(index) => index,
+            None => return first.color,
+        };
+        let stop = self.stops[index];
+        match self.stops.get(index + 1) {
+            Some(next) if self.blend => {
+                let t = (value - stop.value) / (next.value - stop.value);
+                stop.color.lerp(next.color, t)
+            }
+            _ => stop.color,
+        }
+    }
+
+    /// How far `value` is from the first stop to the last, 0.0 - 1.0.
+    pub fn fraction(&self, value: f64) -> f64 {
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return 0.0;
+        };
+        match last.value > first.value {
+            true => ((value - first.value) / (last.value - first.value)).clamp(0.0, 1.0),
+            false => (value >= first.value) as u8 as f64,
+        }
+    }
+}
+
+/// How a [`Gauge`] spreads a reading over the zones.
+#[derive(Debug, Clone, Copy, Default, ValueEnum, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GaugeStyle {
+    /// Every zone shows the color of the reading.
+    #[default]
+    Solid,
+    /// The zones fill up like a bar, in the color of the reading.
+    Bar,
+}
+
+/// Fills the zones up to `fraction` (0.0 - 1.0) with `fill` and shows `empty`
+/// on the rest. The zone the bar ends in is blended between the two, so with
+/// a black `empty` it's lit partially.
+pub fn bar(fraction: f64, fill: RGB, empty: RGB, direction: Direction) -> Frame {
+    let filled = fraction.clamp(0.0, 1.0) * 4.0;
+    std::array::from_fn(|zone| {
+        let position = match direction {
+            Direction::LeftToRight => zone,
+            Direction::RightToLeft => 3 - zone,
+        };
+        empty.lerp(fill, (filled - position as f64).clamp(0.0, 1.0))
+    })
+}
+
+/// Averages `values`, e.g. per-core loads, into one value per zone, the first
+/// values going to the first zone. With fewer than four values some repeat.
+pub fn spread(values: &[f64]) -> [f64; 4] {
+    std::array::from_fn(|zone| {
+        let start = zone * values.len() / 4;
+        let end = ((zone + 1) * values.len() / 4).max(start + 1);
+        match values.get(start..end.min(values.len())) {
+            Some(group) if !group.is_empty() => group.iter().sum::<f64>() / group.len() as f64,
+            _ => 0.0,
+        }
+    })
+}
+
+/// Shows readings on a [`ColorScale`], with hysteresis so a reading sitting
+/// right at a threshold doesn't flicker between colors.
+#[derive(Debug, Clone)]
+pub struct Gauge {
+    pub scale: ColorScale,
+    pub style: GaugeStyle,
+    pub direction: Direction,
+    /// How far a falling reading has to drop below a threshold it crossed
+    /// before the gauge follows it.
+    pub hysteresis: f64,
+    held: Option<f64>,
+}
+
+impl Gauge {
+    pub fn new(scale: ColorScale, style: GaugeStyle, hysteresis: f64) -> Self {
+        Self {
+            scale,
+            style,
+            direction: Direction::default(),
+            hysteresis: hysteresis.max(0.0),
+            held: None,
+        }
+    }
+
+    /// Feeds in a new reading and returns the value to show for it.
+    ///
+    /// Rising readings are followed right away. A falling one stays on the
+    /// threshold it just dropped below until it's `hysteresis` under it.
+    pub fn update(&mut self, reading: f64) -> f64 {
+        let value = match self.held {
+            Some(held) if reading < held => self
+                .scale
+                .stops()
+                .iter()
+                .map(|stop| stop.value)
+                .filter(|&threshold| {
+                    threshold <= held
+                        && reading < threshold
+                        && reading > threshold - self.hysteresis
+                })
+                .fold(reading, f64::max),
+            _ => reading,
+        };
+        self.held = Some(value);
+        value
+    }
+
+    /// The zone colors for a value returned by [`Gauge::update`].
+    pub fn frame(&self, value: f64) -> Frame {
+        let color = self.scale.color(value);
+        match self.style {
+            GaugeStyle::Solid => [color; 4],
+            GaugeStyle::Bar => bar(
+                self.scale.fraction(value),
+                color,
+                RGB::BLACK,
+                self.direction,
+            ),
+        }
+    }
+
+    /// One reading per zone, each colored on its own. Hysteresis and the
+    /// style only apply to single readings.
+    pub fn zones(&self, readings: [f64; 4]) -> Frame {
+        let colors = readings.map(|reading| self.scale.color(reading));
+        match self.direction {
+            Direction::LeftToRight => colors,
+            Direction::RightToLeft => [colors[3], colors[2], colors[1], colors[0]],
+        }
+    }
+
+    /// Updates the gauge with `reading` and returns what to show.
+    pub fn show(&mut self, reading: f64) -> Frame {
+        let value = self.update(reading);
+        self.frame(value)
+    }
+}
+
+/// Shows a rate like network throughput as a brightness. The scale is
+/// logarithmic so a trickle and a download both register, and the level fades
+/// out instead of going dark the moment the activity stops.
+#[derive(Debug, Clone)]
+pub struct Activity {
+    /// Rate at which the zones start to light up.
+    pub min: f64,
+    /// Rate shown at full brightness.
+    pub max: f64,
+    /// Time for the level to halve once the rate drops.
+    pub decay: Duration,
+    level: f64,
+}
+
+impl Activity {
+    pub fn new(min: f64, max: f64, decay: Duration) -> Self {
+        Self {
+            min,
+            max,
+            decay,
+            level: 0.0,
+        }
+    }
+
+    /// Where `rate` falls between `min` and `max` on a log scale, 0.0 - 1.0.
+    pub fn scale(&self, rate: f64) -> f64 {
+        if rate <= self.min || self.min <= 0.0 {
+            return 0.0;
+        }
+        if self.max <= self.min {
+            return 1.0;
+        }
+        ((rate / self.min).ln() / (self.max / self.min).ln()).clamp(0.0, 1.0)
+    }
+
+    /// Feeds in the current rate, `elapsed` after the previous call, and
+    /// returns the level to show. Rising rates are followed right away,
+    /// falling ones decay toward the new level.
+    pub fn update(&mut self, rate: f64, elapsed: Duration) -> f64 {
+        let decayed = match self.decay.is_zero() {
+            true => 0.0,
+            false => self.level * 0.5f64.powf(elapsed.as_secs_f64() / self.decay.as_secs_f64()),
+        };
+        self.level = self.scale(rate).max(decayed);
+        self.level
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GREEN: RGB = RGB::new(0, 255, 0);
+    const YELLOW: RGB = RGB::new(255, 255, 0);
+    const RED: RGB = RGB::new(255, 0, 0);
+
+    fn scale(blend: bool) -> ColorScale {
+        let stops = ["85=#ff0000", "40=#00ff00", "70=#ffff00"];
+        ColorScale::new(stops.iter().map(|s| s.parse().unwrap()).collect(), blend)
+    }
+
+    #[test]
+    fn parses_stops() {
+        let stop: Stop = "85 = red".parse().unwrap();
+        assert_eq!(
+            stop,
+            Stop {
+                value: 85.0,
+                color: RED
+            }
+        );
+        assert!("85".parse::<Stop>().is_err());
+        assert!("hot=red".parse::<Stop>().is_err());
+        assert!("inf=red".parse::<Stop>().is_err());
+    }
+
+    #[test]
+    fn colors_jump_or_blend_between_stops() {
+        assert_eq!(scale(false).color(20.0), GREEN);
+        assert_eq!(scale(false).color(75.0), YELLOW);
+        assert_eq!(scale(false).color(99.0), RED);
+        assert_eq!(scale(true).color(55.0), RGB::new(128, 255, 0));
+        assert_eq!(scale(true).fraction(62.5), 0.5);
+    }
+
+    #[test]
+    fn falling_readings_hold_the_threshold_they_crossed() {
+        let mut gauge = Gauge::new(scale(false), GaugeStyle::Solid, 3.0);
+
+        assert_eq!(gauge.update(71.0), 71.0);
+        // just under 70 still shows yellow
+        assert_eq!(gauge.update(69.0), 70.0);
+        assert_eq!(gauge.show(68.0), [YELLOW; 4]);
+        // until it's the hysteresis below it
+        assert_eq!(gauge.update(66.0), 66.0);
+        assert_eq!(gauge.show(68.0), [GREEN; 4]);
+        // rising readings are followed right away
+        assert_eq!(gauge.update(70.0), 70.0);
+    }
+
+    #[test]
+    fn bars_fill_from_the_direction() {
+        let half = bar(0.375, RED, RGB::BLACK, Direction::LeftToRight);
+        assert_eq!(half, [RED, RGB::new(128, 0, 0), RGB::BLACK, RGB::BLACK]);
+        let half = bar(0.375, RED, RGB::BLACK, Direction::RightToLeft);
+        assert_eq!(half, [RGB::BLACK, RGB::BLACK, RGB::new(128, 0, 0), RED]);
+    }
+
+    #[test]
+    fn spreads_values_over_the_zones() {
+        assert_eq!(
+            spread(&[1.0, 3.0, 5.0, 7.0, 9.0, 11.0, 13.0, 15.0]),
+            [2.0, 6.0, 10.0, 14.0]
+        );
+        assert_eq!(spread(&[1.0, 2.0]), [1.0, 1.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn activity_is_logarithmic_and_decays() {
+        let mut activity = Activity::new(1000.0, 1_000_000.0, Duration::from_secs(1));
+        assert_eq!(activity.scale(500.0), 0.0);
+        assert!((activity.scale(31_622.8) - 0.5).abs() < 1e-6);
+        assert_eq!(activity.scale(1e9), 1.0);
+
+        assert_eq!(activity.update(1_000_000.0, Duration::ZERO), 1.0);
+        assert_eq!(activity.update(0.0, Duration::from_secs(1)), 0.5);
+        assert_eq!(activity.update(0.0, Duration::from_secs(2)), 0.125);
+    }
+}
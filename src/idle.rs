@@ -0,0 +1,312 @@
+//! Noticing when nobody is using the machine, from the input devices or from
+//! what the desktop tells logind.
+
+use crate::error::{Error, Result};
+use crate::sensors::numbered_entries;
+use crate::sensors::power::PowerSource;
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// input event types that mean someone is there, see linux/input-event-codes.h
+const EV_KEY: u16 = 0x01;
+const EV_REL: u16 = 0x02;
+const EV_ABS: u16 = 0x03;
+
+// how long a logind answer is reused, so it isn't asked on every poll
+const LOGIND_CACHE: Duration = Duration::from_secs(1);
+
+/// One `struct input_event` as read from `/dev/input/event*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputEvent {
+    /// When the kernel saw it.
+    pub time: Duration,
+    pub kind: u16,
+    pub code: u16,
+    pub value: i32,
+}
+
+impl InputEvent {
+    /// Size of the struct on this platform, a `timeval` and 8 bytes.
+    pub const SIZE: usize = std::mem::size_of::<libc::timeval>() + 8;
+
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let bytes = bytes.get(..Self::SIZE)?;
+        let half = std::mem::size_of::<libc::timeval>() / 2;
+        let word = |at: usize| -> Option<u64> {
+            let word = bytes.get(at..at + half)?;
+            Some(match half {
+                4 => u32::from_ne_bytes(word.try_into().ok()?) as u64,
+                _ => u64::from_ne_bytes(word.try_into().ok()?),
+            })
+        };
+        let field = &bytes[half * 2..];
+        Some(Self {
+            time: Duration::from_secs(word(0)?) + Duration::from_micros(word(half)?),
+            kind: u16::from_ne_bytes([field[0], field[1]]),
+            code: u16::from_ne_bytes([field[2], field[3]]),
+            value: i32::from_ne_bytes([field[4], field[5], field[6], field[7]]),
+        })
+    }
+
+    /// Keys, buttons and pointer movement, not the sync and misc reports
+    /// around them.
+    pub fn is_input(&self) -> bool {
+        matches!(self.kind, EV_KEY | EV_REL | EV_ABS)
+    }
+}
+
+/// Something that knows how long ago the last input was.
+pub trait IdleSource {
+    fn idle_time(&mut self) -> Result<Duration>;
+}
+
+/// Reads the event devices directly, which needs root or the `input` group.
+/// Devices plugged in later aren't watched.
+pub struct InputDevices {
+    last_input: Arc<Mutex<Instant>>,
+}
+
+impl InputDevices {
+    /// Watches every `event<n>` in `dir`, usually `/dev/input`.
+    pub fn open(dir: &Path) -> Result<Self> {
+        let last_input = Arc::new(Mutex::new(Instant::now()));
+        let mut watched = 0;
+        for path in numbered_entries(dir, "event")? {
+            let Ok(mut device) = File::open(&path) else {
+                continue;
+            };
+            let last_input = Arc::clone(&last_input);
+            std::thread::spawn(move || {
+                let mut buffer = [0; InputEvent::SIZE];
+                // ends when the device goes away
+                while device.read_exact(&mut buffer).is_ok() {
+                    if InputEvent::parse(&buffer).is_some_and(|event| event.is_input()) {
+                        *last_input.lock().unwrap_or_else(|err| err.into_inner()) = Instant::now();
+                    }
+                }
+            });
+            watched += 1;
+        }
+
+        match watched {
+            0 => Err(Error::Sensor(format!(
+                "no readable input devices in {}, this needs root or the input group",
+                dir.display()
+            ))),
+            _ => Ok(Self { last_input }),
+        }
+    }
+}
+
+impl IdleSource for InputDevices {
+    fn idle_time(&mut self) -> Result<Duration> {
+        let last_input = *self
+            .last_input
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        Ok(last_input.elapsed())
+    }
+}
+
+/// Follows the `IdleHint` a desktop sets on its logind session. The desktop
+/// decides when that is, so the idle time only starts counting once it has.
+pub struct LogindIdle {
+    session: String,
+    cached: Option<(Instant, Duration)>,
+}
+
+impl LogindIdle {
+    /// Follows `session`, e.g. `auto` for the caller's own.
+    pub fn new(session: &str) -> Self {
+        Self {
+            session: session.to_string(),
+            cached: None,
+        }
+    }
+
+    /// The idle time in `loginctl show-session -p IdleHint -p
+    /// IdleSinceHintMonotonic` output, given the monotonic clock now.
+    pub fn parse(output: &str, now: Duration) -> Option<Duration> {
+        let property = |name: &str| {
+            output
+                .lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+        };
+        match property("IdleHint")? {
+            "yes" => {
+                let since =
+                    Duration::from_micros(property("IdleSinceHintMonotonic")?.parse().ok()?);
+                Some(now.saturating_sub(since))
+            }
+            _ => Some(Duration::ZERO),
+        }
+    }
+}
+
+impl IdleSource for LogindIdle {
+    fn idle_time(&mut self) -> Result<Duration> {
+        if let Some((checked, idle)) = self.cached {
+            if checked.elapsed() < LOGIND_CACHE {
+                // while not idle the time doesn't grow between checks
+                return Ok(match idle.is_zero() {
+                    true => idle,
+                    false => idle + checked.elapsed(),
+                });
+            }
+        }
+
+        let output = Command::new("loginctl")
+            .args(["show-session", &self.session])
+            .args(["-p", "IdleHint", "-p", "IdleSinceHintMonotonic"])
+            .output()
+            .map_err(|err| Error::Sensor(format!("failed to run loginctl: {}", err)))?;
+        let output = String::from_utf8_lossy(&output.stdout);
+        let idle = Self::parse(&output, monotonic_now()).ok_or_else(|| {
+            Error::Sensor(format!(
+                "no idle hint for logind session '{}'",
+                self.session
+            ))
+        })?;
+        self.cached = Some((Instant::now(), idle));
+        Ok(idle)
+    }
+}
+
+/// Replays input captured with `cat /dev/input/eventN > file`, timed as it
+/// was recorded, the first event happening right away.
+pub struct RecordedInput {
+    inputs: Vec<Duration>,
+    start: Instant,
+}
+
+impl RecordedInput {
+    /// Inputs at the given offsets from now.
+    pub fn new(mut inputs: Vec<Duration>) -> Self {
+        inputs.sort();
+        Self {
+            inputs,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn open(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path).map_err(|source| Error::ReadFile {
+            path: path.display().to_string(),
+            source,
+        })?;
+        let times: Vec<Duration> = bytes
+            .chunks_exact(InputEvent::SIZE)
+            .filter_map(InputEvent::parse)
+            .filter(InputEvent::is_input)
+            .map(|event| event.time)
+            .collect();
+        let first = times.iter().min().copied().unwrap_or_default();
+        Ok(Self::new(
+            times.into_iter().map(|time| time - first).collect(),
+        ))
+    }
+}
+
+impl IdleSource for RecordedInput {
+    fn idle_time(&mut self) -> Result<Duration> {
+        let now = self.start.elapsed();
+        let last = self.inputs.iter().rev().find(|&&input| input <= now);
+        Ok(now - last.copied().unwrap_or_default())
+    }
+}
+
+/// How long to wait for input before dimming, depending on the power source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdleTimeouts {
+    pub ac: Duration,
+    pub battery: Duration,
+}
+
+impl IdleTimeouts {
+    pub fn get(&self, source: PowerSource) -> Duration {
+        match source {
+            PowerSource::Ac => self.ac,
+            PowerSource::Battery => self.battery,
+        }
+    }
+}
+
+/// `CLOCK_MONOTONIC`, which logind's idle timestamps are on.
+fn monotonic_now() -> Duration {
+    let mut now = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `now` is a valid timespec that outlives the call
+    match unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) } {
+        0 => Duration::new(now.tv_sec as u64, now.tv_nsec as u32),
+        _ => Duration::ZERO,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(time: Duration, kind: u16, code: u16, value: i32) -> Vec<u8> {
+        let half = std::mem::size_of::<libc::timeval>() / 2;
+        let word = |word: u64| match half {
+            4 => (word as u32).to_ne_bytes().to_vec(),
+            _ => word.to_ne_bytes().to_vec(),
+        };
+        let mut bytes = word(time.as_secs());
+        bytes.extend(word(time.subsec_micros() as u64));
+        bytes.extend(kind.to_ne_bytes());
+        bytes.extend(code.to_ne_bytes());
+        bytes.extend(value.to_ne_bytes());
+        bytes
+    }
+
+    #[test]
+    fn parses_input_events() {
+        let bytes = event(Duration::from_millis(1500), EV_KEY, 30, 1);
+        assert_eq!(bytes.len(), InputEvent::SIZE);
+        let parsed = InputEvent::parse(&bytes).unwrap();
+        assert_eq!(
+            parsed,
+            InputEvent {
+                time: Duration::from_millis(1500),
+                kind: EV_KEY,
+                code: 30,
+                value: 1
+            }
+        );
+        assert!(parsed.is_input());
+        assert!(InputEvent::parse(&bytes[1..]).is_none());
+    }
+
+    #[test]
+    fn recorded_input_starts_at_the_first_event() {
+        let start = Duration::from_secs(5000);
+        let mut bytes = event(start, EV_KEY, 30, 1);
+        // a sync report, which doesn't count as input
+        bytes.extend(event(start + Duration::from_secs(1), 0, 0, 0));
+        bytes.extend(event(start + Duration::from_secs(3600), EV_REL, 0, -4));
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), bytes).unwrap();
+
+        let mut recorded = RecordedInput::open(file.path()).unwrap();
+        assert_eq!(recorded.inputs, [Duration::ZERO, Duration::from_secs(3600)]);
+        assert!(recorded.idle_time().unwrap() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn parses_logind_idle_hints() {
+        let now = Duration::from_secs(100);
+        let idle = "IdleHint=yes\nIdleSinceHintMonotonic=40000000\n";
+        assert_eq!(LogindIdle::parse(idle, now), Some(Duration::from_secs(60)));
+        let busy = "IdleHint=no\nIdleSinceHintMonotonic=0\n";
+        assert_eq!(LogindIdle::parse(busy, now), Some(Duration::ZERO));
+        assert_eq!(LogindIdle::parse("", now), None);
+    }
+}
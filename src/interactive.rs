@@ -1,6 +1,8 @@
-use crate::utils::*;
+use acer_rgb::utils::*;
 
-use crate::{Args, Direction, LightingMode};
+use crate::Args;
+use acer_rgb::easing::Easing;
+use acer_rgb::{Direction, LightingMode};
 use color_eyre::eyre::Result;
 use dialoguer::Input;
 
@@ -17,6 +19,9 @@ pub fn interactive_mode() -> Args {
         println!("Brightness: {}", args.brightness);
         println!("Direction: {:?}", args.direction);
         println!("Color: RGB({}, {}, {})", args.red, args.green, args.blue);
+        for zone_color in &args.zone_color {
+            println!("Zone color: {}", zone_color);
+        }
 
         let confirmation =
             prompt_with_retry("Apply these settings? (Y/n)", "Y", parse_confirmation);
@@ -62,16 +67,23 @@ fn gather_args(prev_args: Option<&Args>) -> Args {
         None
     };
 
-    let brightness = if mode != LightingMode::Static {
-        let default_brightness_str =
-            prev_args.map_or("100".to_string(), |args| args.brightness.to_string());
-        Some(prompt_with_retry(
-            "Brightness (0-100)",
-            &default_brightness_str,
-            |input| parse_u8(input, "Brightness", 0, 100),
-        ))
+    let default_brightness_str =
+        prev_args.map_or("100".to_string(), |args| args.brightness.to_string());
+    let brightness = prompt_with_retry("Brightness (0-100)", &default_brightness_str, |input| {
+        parse_u8(input, "Brightness", 0, 100)
+    });
+
+    let scale_brightness = if mode == LightingMode::Static && brightness < 100 {
+        let default_scale_str = prev_args.map_or("N".to_string(), |args| {
+            if args.scale_brightness { "Y" } else { "N" }.to_string()
+        });
+        prompt_with_retry(
+            "Also dim the colors in software? (y/N)",
+            &default_scale_str,
+            parse_confirmation,
+        )
     } else {
-        None
+        false
     };
 
     let direction = if mode != LightingMode::Static {
@@ -91,31 +103,84 @@ fn gather_args(prev_args: Option<&Args>) -> Args {
         format!("{},{},{}", args.red, args.green, args.blue)
     });
     let (red, green, blue) = prompt_with_retry(
-        "Specify color (#rrggbb, #rgb, rrggbb, or r,g,b)",
+        "Specify color (#rrggbb, #rgb, rrggbb, r,g,b or a name)",
         &default_color_str,
         parse_color,
     );
 
+    let mut zone_color = Vec::new();
+    let mut preview: Vec<(u8, (u8, u8, u8))> = zones
+        .iter()
+        .map(|&zone| (zone, (red, green, blue)))
+        .collect();
+
+    if mode == LightingMode::Static {
+        let default_per_zone_str = prev_args.map_or("N".to_string(), |args| {
+            if args.zone_color.is_empty() { "N" } else { "Y" }.to_string()
+        });
+        let per_zone = prompt_with_retry(
+            "Set a different color per zone? (y/N)",
+            &default_per_zone_str,
+            parse_confirmation,
+        );
+
+        if per_zone {
+            for (zone, color) in preview.iter_mut() {
+                let default_zone_color_str = prev_args
+                    .and_then(|args| {
+                        args.zone_color
+                            .iter()
+                            .filter_map(|input| parse_zone_color(input).ok())
+                            .find(|(z, _)| z == zone)
+                    })
+                    .map_or(format!("{},{},{}", red, green, blue), |(_, (r, g, b))| {
+                        format!("{},{},{}", r, g, b)
+                    });
+                *color = prompt_with_retry(
+                    &format!("Color for zone {}", zone),
+                    &default_zone_color_str,
+                    parse_color,
+                );
+                if *color != (red, green, blue) {
+                    zone_color.push(format!("{}={},{},{}", zone, color.0, color.1, color.2));
+                }
+            }
+        }
+    }
+
     let dry_run = prompt_with_retry("Debug mode? (y/N)", "N", parse_confirmation);
 
     if mode == LightingMode::Static {
-        preview_static_mode(zones.clone(), red, green, blue);
+        for (_, color) in preview.iter_mut() {
+            *color = scale_color(*color, brightness);
+        }
+        preview_static_mode(&preview);
     }
 
     Args {
+        command: None,
         mode,
         zones,
         speed: speed.unwrap_or(4),
-        brightness: brightness.unwrap_or(100),
+        brightness,
+        scale_brightness,
         direction: direction.unwrap_or(Direction::LeftToRight),
         red,
         green,
         blue,
         color: None,
+        zone_color,
         save: None,
         load: None,
         list: false,
         dry_run,
+        output_file: None,
+        device: None,
+        static_device: None,
+        state_file: None,
+        socket: None,
+        transition: None,
+        easing: Easing::default(),
         interactive: false,
     }
 }
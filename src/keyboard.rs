@@ -0,0 +1,115 @@
+use crate::backend::{Backend, CharDevice, Recorder};
+use crate::encoder::{encode, encode_dynamic, encode_static, DevicePayload};
+use crate::error::Result;
+use crate::state::LightingState;
+use crate::types::{Brightness, Direction, LightingMode, Speed, Zone, RGB};
+
+/// Writes encoded payloads through a [`Backend`].
+///
+/// In dry run mode payloads only go to a [`Recorder`], nothing is opened or
+/// written.
+pub struct KeyboardController {
+    backend: Box<dyn Backend>,
+}
+
+impl KeyboardController {
+    pub fn new(dry_run: bool) -> Self {
+        if dry_run {
+            Self::with_backend(Recorder::new())
+        } else {
+            Self::with_backend(CharDevice::new())
+        }
+    }
+
+    pub fn with_backend(backend: impl Backend + 'static) -> Self {
+        Self {
+            backend: Box::new(backend),
+        }
+    }
+
+    /// Writes each payload to the device it is meant for, in order.
+    pub fn write(&mut self, payloads: &[DevicePayload]) -> Result<()> {
+        for payload in payloads {
+            self.backend.write(payload)?;
+        }
+        Ok(())
+    }
+
+    pub fn apply_static(
+        &mut self,
+        zone_colors: &[(Zone, RGB)],
+        brightness: Brightness,
+    ) -> Result<Vec<DevicePayload>> {
+        let payloads = encode_static(zone_colors, brightness);
+        self.write(&payloads)?;
+        Ok(payloads)
+    }
+
+    pub fn apply_dynamic(
+        &mut self,
+        mode: LightingMode,
+        speed: Speed,
+        brightness: Brightness,
+        direction: Direction,
+        color: RGB,
+    ) -> Result<Vec<DevicePayload>> {
+        let payloads = vec![encode_dynamic(mode, speed, brightness, direction, color)];
+        self.write(&payloads)?;
+        Ok(payloads)
+    }
+
+    pub fn apply(&mut self, state: &LightingState) -> Result<Vec<DevicePayload>> {
+        let payloads = encode(state);
+        self.write(&payloads)?;
+        Ok(payloads)
+    }
+}
+
+/// A handle to the keyboard.
+///
+/// ```no_run
+/// use acer_rgb::{Brightness, Keyboard, LightingState, Zone, RGB};
+///
+/// let mut keyboard = Keyboard::open();
+/// let state = LightingState::new_static(
+///     vec![(Zone::new(1)?, RGB::new(255, 0, 0)), (Zone::new(4)?, "#00f".parse()?)],
+///     Brightness::new(60)?,
+/// );
+/// keyboard.apply(&state)?;
+/// # Ok::<(), acer_rgb::Error>(())
+/// ```
+pub struct Keyboard {
+    controller: KeyboardController,
+}
+
+impl Keyboard {
+    /// Writes to the real devices.
+    pub fn open() -> Self {
+        Self {
+            controller: KeyboardController::new(false),
+        }
+    }
+
+    /// Only encodes payloads without writing them anywhere.
+    pub fn dry_run() -> Self {
+        Self {
+            controller: KeyboardController::new(true),
+        }
+    }
+
+    /// Writes through a custom backend, e.g. a [`Recorder`] in tests.
+    pub fn with_backend(backend: impl Backend + 'static) -> Self {
+        Self {
+            controller: KeyboardController::with_backend(backend),
+        }
+    }
+
+    /// Shows `state` on the keyboard, returning the payloads that were written.
+    pub fn apply(&mut self, state: &LightingState) -> Result<Vec<DevicePayload>> {
+        self.controller.apply(state)
+    }
+
+    pub fn controller(&mut self) -> &mut KeyboardController {
+        &mut self.controller
+    }
+}
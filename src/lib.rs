@@ -0,0 +1,41 @@
+//! Control the RGB keyboard on Acer Predator and Helios laptops.
+//!
+//! Talks to the character devices created by the
+//! [acer-predator-turbo-and-rgb-keyboard-linux-module](https://github.com/JafarAkhondali/acer-predator-turbo-and-rgb-keyboard-linux-module).
+//! A [`LightingState`] describes what the keyboard should show, the [`encoder`]
+//! turns it into device payloads and a [`Keyboard`] writes them through a
+//! [`Backend`].
+
+pub mod ambient;
+pub mod backend;
+pub mod clock;
+pub mod config;
+pub mod daemon;
+pub mod easing;
+pub mod effects;
+pub mod encoder;
+pub mod error;
+pub mod gauge;
+pub mod idle;
+pub mod keyboard;
+pub mod notify;
+pub mod persist;
+pub mod rules;
+pub mod sensors;
+pub mod state;
+pub mod systemd;
+pub mod timeline;
+pub mod timer;
+pub mod transition;
+pub mod types;
+pub mod utils;
+
+pub use backend::{Backend, CharDevice, DevicePaths, FileBackend, Recorder};
+pub use config::Config;
+pub use encoder::{Device, DevicePayload};
+pub use error::{Error, Result};
+pub use keyboard::{Keyboard, KeyboardController};
+pub use persist::AppliedState;
+pub use state::LightingState;
+pub use types::{Brightness, Direction, LightingMode, Speed, Zone, ALL_ZONES, RGB};
+pub use utils::parse_color;
@@ -1,166 +1,44 @@
+mod commands;
 mod interactive;
-mod utils;
 
-use utils::{parse_color, preview_static_mode};
+use acer_rgb::ambient::{AutoBrightness, BrightnessCurve, CurvePoint};
+use acer_rgb::clock::{ClockDisplay, ClockFace, FixedClock, LocalClock, TimeOfDay};
+use acer_rgb::config::SwitchRules;
+use acer_rgb::daemon::{default_socket_path, Client, Daemon, Event};
+use acer_rgb::easing::Easing;
+use acer_rgb::encoder::DevicePayload;
+use acer_rgb::gauge::{GaugeStyle, Stop};
+use acer_rgb::idle::{IdleSource, IdleTimeouts, InputDevices, LogindIdle, RecordedInput};
+use acer_rgb::sensors::light::LightSensor;
+use acer_rgb::state::resolve_zone_colors;
+use acer_rgb::systemd::{write_units, SERVICE_NAME};
+use acer_rgb::timer::{Schedule, TimerAction};
+use acer_rgb::utils::{
+    format_duration, parse_color, parse_duration, parse_rate, preview_static_mode,
+};
+use acer_rgb::{
+    AppliedState, Brightness, CharDevice, Config, DevicePaths, Direction, FileBackend,
+    KeyboardController, LightingMode, LightingState, Speed, Zone, ALL_ZONES, RGB,
+};
 
 use interactive::interactive_mode;
 
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand};
 use color_eyre::eyre::{eyre, Result, WrapErr};
 
 use serde::{Deserialize, Serialize};
-use std::fs::{File, OpenOptions};
-use std::io::Write;
-use std::path::PathBuf;
+use std::fs::File;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-
-const PAYLOAD_SIZE: usize = 16;
-const PAYLOAD_SIZE_STATIC: usize = 4;
-const CHARACTER_DEVICE: &str = "/dev/acer-gkbbl-0";
-const CHARACTER_DEVICE_STATIC: &str = "/dev/acer-gkbbl-static-0";
-const ALL_ZONES: [Zone; 4] = [Zone(1), Zone(2), Zone(3), Zone(4)];
-
-#[derive(Debug, Clone, Copy, ValueEnum, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "lowercase")]
-enum LightingMode {
-    Static,
-    Breath,
-    Neon,
-    Wave,
-    Shifting,
-    Zoom,
-}
-
-impl FromStr for LightingMode {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "static" => Ok(LightingMode::Static),
-            "breath" => Ok(LightingMode::Breath),
-            "neon" => Ok(LightingMode::Neon),
-            "wave" => Ok(LightingMode::Wave),
-            "shifting" => Ok(LightingMode::Shifting),
-            "zoom" => Ok(LightingMode::Zoom),
-            _ => Err(format!("'{}' is not a valid lighting mode", s)),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, ValueEnum, Serialize, Deserialize)]
-#[serde(rename_all = "kebab-case")]
-enum Direction {
-    RightToLeft = 1,
-    LeftToRight = 2,
-}
-
-impl FromStr for Direction {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "right-to-left" => Ok(Direction::RightToLeft),
-            "left-to-right" => Ok(Direction::LeftToRight),
-            "rl" => Ok(Direction::RightToLeft),
-            "lr" => Ok(Direction::LeftToRight),
-            _ => Err(format!("'{}' is not a valid direction", s)),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-struct RGB {
-    red: u8,
-    green: u8,
-    blue: u8,
-}
-
-impl RGB {
-    fn new(red: u8, green: u8, blue: u8) -> Self {
-        Self { red, green, blue }
-    }
-
-    fn to_bytes(self) -> [u8; 3] {
-        [self.red, self.green, self.blue]
-    }
-}
-
-impl std::fmt::Display for RGB {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "RGB({}, {}, {})", self.red, self.green, self.blue)
-    }
-}
-
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-struct Zone(u8);
-
-impl Zone {
-    fn new(zone: u8) -> Result<Self> {
-        match zone {
-            0 => Ok(Self(0)),
-            1..=4 => Ok(Self(zone)),
-            _ => Err(eyre!("Zone must be 0 (all zones) or between 1 and 4")),
-        }
-    }
-
-    fn to_mask(self) -> u8 {
-        1 << (self.0 - 1)
-    }
-
-    fn to_u8(self) -> u8 {
-        self.0
-    }
-
-    fn zones_to_u8s(zones: Vec<Self>) -> Vec<u8> {
-        zones.into_iter().map(|zone| zone.to_u8()).collect()
-    }
-}
-
-impl std::fmt::Display for Zone {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Zone {}", self.0)
-    }
-}
-
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-struct Speed(u8);
-
-impl Speed {
-    fn new(speed: u8) -> Result<Self> {
-        match speed <= 9 {
-            true => Ok(Self(speed)),
-            false => Err(eyre!("Speed should be between 0 and 9")),
-        }
-    }
-}
-
-impl std::fmt::Display for Speed {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Speed {}", self.0)
-    }
-}
-
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-struct Brightness(u8);
-
-impl Brightness {
-    fn new(brightness: u8) -> Result<Self> {
-        match brightness <= 100 {
-            true => Ok(Self(brightness)),
-            false => Err(eyre!("Brightness must be between 0 and 100")),
-        }
-    }
-}
-
-impl std::fmt::Display for Brightness {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Brightness {}%", self.0)
-    }
-}
+use std::time::Duration;
 
 #[derive(Parser, Debug, Serialize, Deserialize)]
 #[command(about = "Control Predator keyboard RGB lighting")]
 struct Args {
+    #[command(subcommand)]
+    #[serde(skip)]
+    command: Option<Command>,
+
     #[arg(
         short,
         long,
@@ -184,11 +62,19 @@ struct Args {
     #[arg(
         short = 'y',
         long,
+        global = true,
         default_value = "100",
         help = "Brightness percentage (0-100)"
     )]
     brightness: u8,
 
+    #[arg(
+        long,
+        help = "Also scale static colors by --brightness, for firmware that ignores it"
+    )]
+    #[serde(default)]
+    scale_brightness: bool,
+
     #[arg(
         short = 'd',
         long,
@@ -199,10 +85,18 @@ struct Args {
 
     #[arg(
         long,
-        help = "Color in #rrggbb, #rgb, rrggbb, or r,g,b format. overwrites -r,-g,-b."
+        help = "Color in #rrggbb, #rgb, rrggbb, r,g,b format or a name (e.g., red). overwrites -r,-g,-b."
     )]
     color: Option<String>,
 
+    #[arg(
+        long = "zone-color",
+        value_name = "ZONE=COLOR",
+        help = "Color for a single zone as zone=color (e.g., 1=#ff0000). can be repeated, overwrites --color for that zone."
+    )]
+    #[serde(default)]
+    zone_color: Vec<String>,
+
     #[arg(
         short = 'r',
         long,
@@ -236,164 +130,853 @@ struct Args {
     #[arg(long, help = "List available saved profiles")]
     list: bool,
 
-    #[arg(long, help = "Perform a dry run without applying changes")]
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        help = "Fade from the last applied state over this long (e.g., 500ms)"
+    )]
+    #[serde(skip)]
+    transition: Option<Duration>,
+
+    #[arg(
+        long,
+        default_value = "linear",
+        hide_possible_values = true,
+        help = "Easing of the --transition fade (linear, ease-in, ease-out, ease-in-out, cubic, step)"
+    )]
+    #[serde(skip)]
+    easing: Easing,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Perform a dry run without applying changes"
+    )]
     dry_run: bool,
 
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Append the raw payloads to a file or FIFO instead of the devices"
+    )]
+    #[serde(skip)]
+    output_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Dynamic character device [env: ACER_RGB_DEVICE] [default: /dev/acer-gkbbl-0]"
+    )]
+    #[serde(skip)]
+    device: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Static character device [env: ACER_RGB_STATIC_DEVICE] [default: /dev/acer-gkbbl-static-0]"
+    )]
+    #[serde(skip)]
+    static_device: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "File recording the last applied state [default: ~/.local/state/predator/state.json]"
+    )]
+    #[serde(skip)]
+    state_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Control socket of the daemon [default: $XDG_RUNTIME_DIR/acer-rgb.sock]"
+    )]
+    #[serde(skip)]
+    socket: Option<PathBuf>,
+
     #[arg(short, long, help = "Interactive mode to set configurations")]
     interactive: bool,
 }
 
-fn convert_zones(zones: &[u8]) -> Result<Vec<Zone>> {
-    if zones.len() == 1 && zones[0] == 0 {
-        return Ok(ALL_ZONES.to_vec());
-    }
-    zones.iter().map(|&z| Zone::new(z)).collect()
-}
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    #[command(about = "Show the last applied lighting state")]
+    Status {
+        #[arg(long, help = "Print the state as JSON")]
+        json: bool,
+
+        #[arg(
+            long,
+            help = "Keep printing the state whenever the daemon applies a new one"
+        )]
+        follow: bool,
+    },
+
+    #[command(about = "Write the last applied payloads again, e.g. after boot or resume")]
+    Restore,
+
+    #[command(about = "Own the keyboard and take requests on the control socket")]
+    Daemon,
+
+    #[command(about = "Play a software animation on the zones")]
+    Effect {
+        #[command(subcommand)]
+        effect: EffectKind,
+
+        #[arg(long, default_value = "30", help = "Frames per second")]
+        fps: u32,
+
+        #[arg(
+            long,
+            value_parser = parse_duration,
+            help = "Stop after this long (e.g., 30s), runs until interrupted otherwise"
+        )]
+        duration: Option<Duration>,
+    },
+
+    #[command(about = "Play a keyframe animation from a timeline file")]
+    Play {
+        #[arg(help = "Timeline file (JSON)")]
+        file: PathBuf,
+
+        #[arg(long, default_value = "30", help = "Frames per second")]
+        fps: u32,
+    },
+
+    #[command(about = "Show the time as binary digits or a hue of the day")]
+    Clock {
+        #[arg(long, default_value = "binary-hour", help = "How the time is drawn")]
+        face: ClockFace,
+
+        #[arg(long, default_value = "white", value_parser = RGB::from_str, help = "Color of the set bits")]
+        on_color: RGB,
+
+        #[arg(long, default_value = "black", value_parser = RGB::from_str, help = "Color of the cleared bits")]
+        off_color: RGB,
+
+        #[arg(long, value_name = "HH:MM", value_parser = TimeOfDay::from_str, help = "Show this time once instead of following the clock")]
+        at: Option<TimeOfDay>,
+    },
+
+    #[command(about = "Light the zones from a live reading until interrupted")]
+    Monitor {
+        #[command(subcommand)]
+        monitor: MonitorKind,
+
+        #[arg(long, global = true, default_value = "1s", value_parser = parse_duration, help = "Time between readings")]
+        interval: Duration,
+
+        #[arg(
+            long,
+            global = true,
+            value_name = "PATH",
+            default_value = acer_rgb::sensors::SYSFS_ROOT,
+            help = "Where sysfs is mounted"
+        )]
+        sysfs_root: PathBuf,
+
+        #[arg(
+            long,
+            global = true,
+            value_name = "PATH",
+            default_value = acer_rgb::sensors::PROCFS_ROOT,
+            help = "Where procfs is mounted"
+        )]
+        procfs_root: PathBuf,
+    },
+
+    #[command(about = "Follow the ambient light sensor, dimming the keyboard in bright rooms")]
+    AutoBrightness {
+        #[arg(long, help = "IIO device id or name [default: the first light sensor]")]
+        sensor: Option<String>,
+
+        #[arg(
+            long = "point",
+            value_name = "LUX=PERCENT",
+            value_parser = CurvePoint::from_str,
+            help = "Brightness at a light level (e.g., 500=20). can be repeated, replaces the default curve"
+        )]
+        points: Vec<CurvePoint>,
+
+        #[arg(long, default_value = "0", value_parser = clap::value_parser!(u8).range(0..=100), help = "Lowest brightness to go to")]
+        min: u8,
+
+        #[arg(long, default_value = "100", value_parser = clap::value_parser!(u8).range(0..=100), help = "Highest brightness to go to")]
+        max: u8,
+
+        #[arg(long, default_value = "3s", value_parser = parse_duration, help = "How slowly the brightness follows the light")]
+        smoothing: Duration,
+
+        #[arg(long, default_value = "500ms", value_parser = parse_duration, help = "Time between readings")]
+        interval: Duration,
+
+        #[arg(
+            long,
+            value_name = "PATH",
+            default_value = acer_rgb::sensors::SYSFS_ROOT,
+            help = "Where sysfs is mounted"
+        )]
+        sysfs_root: PathBuf,
+    },
+
+    #[command(about = "Switch profiles when the power source or lid changes, by the config rules")]
+    AutoProfile {
+        #[arg(long, default_value = "1s", value_parser = parse_duration, help = "Time between checks")]
+        interval: Duration,
+
+        #[arg(
+            long,
+            value_name = "PATH",
+            default_value = acer_rgb::sensors::SYSFS_ROOT,
+            help = "Where sysfs is mounted"
+        )]
+        sysfs_root: PathBuf,
+
+        #[arg(
+            long,
+            value_name = "PATH",
+            default_value = acer_rgb::sensors::PROCFS_ROOT,
+            help = "Where procfs is mounted"
+        )]
+        procfs_root: PathBuf,
+    },
+
+    #[command(about = "Dim the keyboard after a while without input, until the next keypress")]
+    Idle {
+        #[arg(long, default_value = "5m", value_parser = parse_duration, help = "Time without input before dimming")]
+        timeout: Duration,
+
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration, help = "Time without input while on battery [default: --timeout]")]
+        battery_timeout: Option<Duration>,
+
+        #[arg(long, default_value = "0", value_parser = clap::value_parser!(u8).range(0..=100), help = "Brightness to dim to, 0 turns it off")]
+        dim_to: u8,
+
+        #[arg(long, default_value = "2s", value_parser = parse_duration, help = "Length of the fade out")]
+        fade: Duration,
+
+        #[arg(
+            long,
+            conflicts_with = "replay",
+            help = "Follow the idle hint the desktop gives logind instead of the input devices"
+        )]
+        logind: bool,
+
+        #[arg(long, default_value = "auto", help = "logind session to follow")]
+        session: String,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Replay input captured with `cat /dev/input/eventN > FILE` instead"
+        )]
+        replay: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "PATH",
+            default_value = "/dev/input",
+            help = "Where the input event devices are"
+        )]
+        input_dir: PathBuf,
+
+        #[arg(
+            long,
+            value_name = "PATH",
+            default_value = acer_rgb::sensors::SYSFS_ROOT,
+            help = "Where sysfs is mounted"
+        )]
+        sysfs_root: PathBuf,
+    },
+
+    #[command(about = "Blink some zones, then put back what was showing before")]
+    Notify {
+        #[arg(long, default_value = "white", value_parser = RGB::from_str, help = "Blink color")]
+        color: RGB,
+
+        #[arg(
+            short = 'z',
+            long,
+            value_delimiter = ',',
+            default_value = "0",
+            help = "Zones to blink (0 for all, 1-4 for specific zones)"
+        )]
+        zones: Vec<u8>,
 
-#[derive(Debug)]
-struct DevicePayload {
-    device: String,
-    payload: Vec<u8>,
+        #[arg(long, default_value = "3", help = "How many times to blink")]
+        times: u32,
+
+        #[arg(long, default_value = "300ms", value_parser = parse_duration, help = "Length of one blink")]
+        period: Duration,
+    },
+
+    #[command(about = "Fill the zones like a progress bar from percentages read on stdin")]
+    Progress {
+        #[arg(long, default_value = "green", value_parser = RGB::from_str, help = "Color of the filled part")]
+        fill_color: RGB,
+
+        #[arg(long, default_value = "black", value_parser = RGB::from_str, help = "Color of the empty part")]
+        empty_color: RGB,
+
+        #[arg(
+            short = 'd',
+            long,
+            default_value = "left-to-right",
+            help = "Direction the bar fills in"
+        )]
+        direction: Direction,
+    },
+
+    #[command(about = "Run a command with a busy animation, then flash its result")]
+    Run {
+        #[arg(long, default_value = "#0080ff", value_parser = RGB::from_str, help = "Color of the busy animation")]
+        busy_color: RGB,
+
+        #[arg(long, default_value = "green", value_parser = RGB::from_str, help = "Flash color when the command succeeds")]
+        success_color: RGB,
+
+        #[arg(long, default_value = "red", value_parser = RGB::from_str, help = "Flash color when the command fails")]
+        failure_color: RGB,
+
+        #[arg(long, default_value = "3", help = "How many times to flash")]
+        times: u32,
+
+        #[arg(long, default_value = "300ms", value_parser = parse_duration, help = "Length of one flash")]
+        period: Duration,
+
+        #[arg(
+            required = true,
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            value_name = "COMMAND",
+            help = "Command to run, after --"
+        )]
+        command: Vec<String>,
+    },
+
+    #[command(about = "Count down by draining the zones, optionally in work/break cycles")]
+    Timer {
+        #[arg(
+            value_parser = parse_duration,
+            required_unless_present_any = ["pause", "resume"],
+            help = "Length of the countdown, or of each work phase (e.g., 25m)"
+        )]
+        duration: Option<Duration>,
+
+        #[arg(long = "break", value_name = "DURATION", value_parser = parse_duration, help = "Break between work phases")]
+        pause_length: Option<Duration>,
+
+        #[arg(long, default_value = "1", help = "Number of work phases")]
+        cycles: u32,
+
+        #[arg(long, default_value = "green", value_parser = RGB::from_str, help = "Color of the work phases")]
+        color: RGB,
+
+        #[arg(long, default_value = "blue", value_parser = RGB::from_str, help = "Color of the breaks")]
+        break_color: RGB,
+
+        #[arg(long, default_value = "red", value_parser = RGB::from_str, help = "Color shifted to near the end, and flashed when a phase is over")]
+        end_color: RGB,
+
+        #[arg(
+            short = 'd',
+            long,
+            default_value = "left-to-right",
+            help = "Direction the zones fill in, they drain the other way"
+        )]
+        direction: Direction,
+
+        #[arg(
+            long,
+            conflicts_with = "resume",
+            help = "Pause the running timer through the daemon"
+        )]
+        pause: bool,
+
+        #[arg(long, help = "Resume the paused timer through the daemon")]
+        resume: bool,
+    },
+
+    #[command(about = "Map the output of a command or file to lighting through a rule table")]
+    Watch {
+        #[arg(
+            long,
+            required_unless_present = "file",
+            conflicts_with = "file",
+            help = "Shell command to run, its output or exit code is matched against the rules"
+        )]
+        command: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "PATH",
+            help = "File to read and match against the rules"
+        )]
+        file: Option<PathBuf>,
+
+        #[arg(long, value_name = "PATH", help = "Rule table (JSON)")]
+        rules: PathBuf,
+
+        #[arg(long, default_value = "5s", value_parser = parse_duration, help = "Time between readings")]
+        interval: Duration,
+
+        #[arg(long, help = "Read and apply once, then exit")]
+        once: bool,
+    },
+
+    #[command(about = "Install a systemd unit and sleep hook that restore the lighting")]
+    InstallUnits {
+        #[arg(long, default_value = acer_rgb::systemd::SYSTEM_UNIT_DIR, help = "Directory for the restore unit")]
+        unit_dir: PathBuf,
+
+        #[arg(long, default_value = acer_rgb::systemd::SYSTEM_SLEEP_DIR, help = "Directory for the systemd-sleep hook")]
+        sleep_dir: PathBuf,
+    },
 }
 
-impl std::fmt::Display for DevicePayload {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Device: {}\nPayload: {:02X?}", self.device, self.payload)
-    }
+#[derive(Subcommand, Debug, Clone)]
+enum EffectKind {
+    #[command(about = "Fade each zone's color in and out")]
+    Breathe {
+        #[arg(long, default_value = "#f03020", value_parser = RGB::from_str, help = "Color of every zone")]
+        color: RGB,
+
+        #[arg(
+            long = "zone-color",
+            value_name = "ZONE=COLOR",
+            help = "Color for a single zone as zone=color (e.g., 1=#ff0000). can be repeated"
+        )]
+        zone_color: Vec<String>,
+
+        #[arg(long, default_value = "3s", value_parser = parse_duration, help = "Length of one breath")]
+        period: Duration,
+    },
+
+    #[command(about = "Rotate through every hue across the zones")]
+    Rainbow {
+        #[arg(long, default_value = "6s", value_parser = parse_duration, help = "Length of one full rotation")]
+        period: Duration,
+
+        #[arg(
+            short = 'd',
+            long,
+            default_value = "left-to-right",
+            help = "Direction of the rotation"
+        )]
+        direction: Direction,
+    },
+
+    #[command(about = "Flash a color on and off")]
+    Strobe {
+        #[arg(long, default_value = "#ffffff", value_parser = RGB::from_str, help = "Flash color")]
+        color: RGB,
+
+        #[arg(long, default_value = "100ms", value_parser = parse_duration, help = "How long each flash lasts")]
+        on: Duration,
+
+        #[arg(long, default_value = "400ms", value_parser = parse_duration, help = "Pause between flashes")]
+        off: Duration,
+    },
+
+    #[command(about = "Flicker like a fire or candle")]
+    Fire {
+        #[arg(long, default_value = "#ff6000", value_parser = RGB::from_str, help = "Color of the flames")]
+        color: RGB,
+
+        #[arg(
+            long,
+            default_value = "0.6",
+            help = "How strongly it flickers (0.0-1.0)"
+        )]
+        flicker: f64,
+    },
+
+    #[command(about = "Scroll a gradient through the given colors")]
+    Gradient {
+        #[arg(long = "color", required = true, value_parser = RGB::from_str, help = "A gradient color. can be repeated")]
+        colors: Vec<RGB>,
+
+        #[arg(long, default_value = "8s", value_parser = parse_duration, help = "Time to scroll through the whole gradient")]
+        period: Duration,
+
+        #[arg(
+            short = 'd',
+            long,
+            default_value = "left-to-right",
+            help = "Direction of the scrolling"
+        )]
+        direction: Direction,
+    },
 }
 
-enum KeyboardController {
-    Real {
-        device: Option<File>,
-        device_static: Option<File>,
+#[derive(Subcommand, Debug, Clone)]
+enum MonitorKind {
+    #[command(about = "Show a temperature, by default the CPU package")]
+    Temp {
+        #[arg(long, help = "Sensor id or name, as shown by --list")]
+        sensor: Option<String>,
+
+        #[arg(long, help = "List the temperature sensors and exit")]
+        list: bool,
+
+        #[command(flatten)]
+        gauge: GaugeArgs,
+    },
+
+    #[command(about = "Show the CPU load as a bar, or per core")]
+    Cpu {
+        #[arg(
+            long,
+            help = "Split the cores over the zones, each colored by its share's load"
+        )]
+        per_core: bool,
+
+        #[command(flatten)]
+        gauge: GaugeArgs,
+    },
+
+    #[command(about = "Show the battery charge as a bar, breathing while it charges")]
+    Battery {
+        #[arg(long, help = "Battery to show, e.g. BAT1 [default: the first one]")]
+        battery: Option<String>,
+
+        #[arg(
+            long,
+            default_value = "15",
+            help = "Pulse when discharging below this percentage"
+        )]
+        warn_below: u8,
+
+        #[arg(long, default_value = "#ff0000", value_parser = RGB::from_str, help = "Color of the low battery pulse")]
+        warning_color: RGB,
+
+        #[arg(long, default_value = "3s", value_parser = parse_duration, help = "Length of one breath while charging")]
+        period: Duration,
+
+        #[command(flatten)]
+        gauge: GaugeArgs,
+    },
+
+    #[command(about = "Show memory use, pulsing when tasks stall waiting for memory")]
+    Memory {
+        #[arg(
+            long,
+            default_value = "10",
+            help = "Pulse when stalled more than this percentage of the last 10s"
+        )]
+        warn_above: f64,
+
+        #[arg(long, default_value = "#ff0000", value_parser = RGB::from_str, help = "Color of the pressure pulse")]
+        warning_color: RGB,
+
+        #[command(flatten)]
+        gauge: GaugeArgs,
+    },
+
+    #[command(about = "Show network traffic, received on zones 1-2 and sent on 3-4")]
+    Net {
+        #[arg(long, help = "Interface to show, e.g. wlan0 [default: all but lo]")]
+        iface: Option<String>,
+
+        #[arg(long, default_value = "green", value_parser = RGB::from_str, help = "Color of received traffic")]
+        rx_color: RGB,
+
+        #[arg(long, default_value = "blue", value_parser = RGB::from_str, help = "Color of sent traffic")]
+        tx_color: RGB,
+
+        #[command(flatten)]
+        activity: ActivityArgs,
+    },
+
+    #[command(about = "Show disk activity, reads on zones 1-2 and writes on 3-4")]
+    Disk {
+        #[arg(
+            value_name = "DEVICE",
+            help = "Block device to show, e.g. nvme0n1 or sda"
+        )]
+        block_device: String,
+
+        #[arg(long, default_value = "green", value_parser = RGB::from_str, help = "Color of reads")]
+        read_color: RGB,
+
+        #[arg(long, default_value = "orange", value_parser = RGB::from_str, help = "Color of writes")]
+        write_color: RGB,
+
+        #[command(flatten)]
+        activity: ActivityArgs,
     },
-    DryRun,
 }
 
-impl KeyboardController {
-    fn new(dry_run: bool) -> Result<Self> {
-        if dry_run {
-            Ok(Self::DryRun)
-        } else {
-            Ok(Self::Real {
-                device: None,
-                device_static: None,
-            })
-        }
-    }
+#[derive(clap::Args, Debug, Clone)]
+struct ActivityArgs {
+    #[arg(
+        long,
+        value_name = "RATE",
+        value_parser = parse_rate,
+        help = "Rate shown at full brightness, e.g. 10M [default: 100M for net, 1G for disk]"
+    )]
+    max: Option<f64>,
 
-    fn open_device(device: &str) -> Result<File> {
-        OpenOptions::new()
-            .write(true)
-            .open(device)
-            .wrap_err_with(|| format!("Failed to open device {}", device))
-    }
+    #[arg(long, default_value = "500ms", value_parser = parse_duration, help = "Time for the light to halve once activity drops")]
+    decay: Duration,
+
+    #[arg(long, default_value = "10", help = "Most keyboard writes per second")]
+    fps: u32,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct GaugeArgs {
+    #[arg(
+        long = "stop",
+        value_name = "VALUE=COLOR",
+        value_parser = Stop::from_str,
+        help = "Color at a reading (e.g., 85=#ff0000). can be repeated, replaces the default stops"
+    )]
+    stops: Vec<Stop>,
+
+    #[arg(
+        long,
+        help = "Jump from one stop color to the next instead of blending"
+    )]
+    steps: bool,
+
+    #[arg(
+        long,
+        help = "Color every zone or fill them like a bar [default: solid, bar for cpu and battery]"
+    )]
+    style: Option<GaugeStyle>,
+
+    #[arg(
+        short = 'd',
+        long,
+        default_value = "left-to-right",
+        help = "Direction the bar fills in"
+    )]
+    direction: Direction,
+
+    #[arg(
+        long,
+        help = "How far a reading has to fall below a stop before the color follows it"
+    )]
+    hysteresis: Option<f64>,
+}
+
+// builds the state to apply from the (possibly loaded) arguments
+fn state_from_args(args: &Args) -> Result<LightingState> {
+    let color = RGB::new(args.red, args.green, args.blue);
+    let speed = Speed::new(args.speed)?;
+    let brightness = Brightness::new(args.brightness)?;
 
-    fn lazy_open_device(&mut self) -> Result<&mut File> {
-        if let Self::Real { device, .. } = self {
-            if device.is_none() {
-                *device = Some(Self::open_device(CHARACTER_DEVICE)?);
+    let state = match args.mode {
+        LightingMode::Static => {
+            let zones = Zone::from_u8s(&args.zones)?;
+            let zone_colors = resolve_zone_colors(&zones, &args.zone_color, color)?;
+            LightingState {
+                scale_brightness: args.scale_brightness,
+                ..LightingState::new_static(zone_colors, brightness)
             }
-            Ok(device.as_mut().unwrap())
-        } else {
-            Err(eyre!("Dry run mode, no real device"))
         }
+        mode => LightingState::new_dynamic(mode, color, speed, brightness, args.direction),
+    };
+    Ok(state)
+}
+
+// the preview shows the color dimmed to the brightness
+fn preview_state(state: &LightingState) {
+    let brightness = state.brightness;
+    let preview: Vec<(u8, (u8, u8, u8))> = ALL_ZONES
+        .iter()
+        .filter_map(|&zone| {
+            let color = state.zone_color(zone)?.scale(brightness);
+            Some((zone.to_u8(), (color.red, color.green, color.blue)))
+        })
+        .collect();
+
+    preview_static_mode(&preview);
+}
+
+fn state_file(args: &Args) -> PathBuf {
+    args.state_file
+        .clone()
+        .unwrap_or_else(AppliedState::default_path)
+}
+
+fn socket_path(args: &Args) -> PathBuf {
+    args.socket.clone().unwrap_or_else(default_socket_path)
+}
+
+// the running daemon, unless this run shouldn't touch the real keyboard
+fn connect_daemon(args: &Args) -> Option<Client> {
+    if args.dry_run || args.output_file.is_some() {
+        return None;
     }
+    Client::connect(socket_path(args)).ok()
+}
 
-    fn lazy_open_static_device(&mut self) -> Result<&mut File> {
-        if let Self::Real { device_static, .. } = self {
-            if device_static.is_none() {
-                *device_static = Some(Self::open_device(CHARACTER_DEVICE_STATIC)?);
-            }
-            Ok(device_static.as_mut().unwrap())
-        } else {
-            Err(eyre!("Dry run mode, no real device"))
-        }
+// draws through the daemon when it's running, so frames shown over the
+// applied state don't race its writes, otherwise to the devices directly
+fn overlay_controller(args: &Args) -> Result<KeyboardController> {
+    match connect_daemon(args) {
+        Some(client) => Ok(KeyboardController::with_backend(client)),
+        None => open_controller(args),
     }
+}
 
-    fn apply_static(&mut self, zones: &[Zone], color: RGB) -> Result<Vec<DevicePayload>> {
-        let mut payloads = Vec::new();
-        let mut static_payloads = Vec::new();
+fn run_daemon(args: &Args) -> Result<()> {
+    let socket = socket_path(args);
+    let daemon = Daemon::new(open_controller(args)?, state_file(args))?;
+    println!("Listening on {}", socket.display());
+    daemon.run(&socket)?;
+    Ok(())
+}
 
-        for &zone in zones {
-            let mut static_payload = [0u8; PAYLOAD_SIZE_STATIC];
-            static_payload[0] = zone.to_mask();
-            let [r, g, b] = color.to_bytes();
-            static_payload[1..4].copy_from_slice(&[r, g, b]);
-            static_payloads.push(static_payload);
+// what the keyboard shows right now, as far as we know
+fn current_state(args: &Args) -> Result<Option<LightingState>> {
+    let applied = match Client::connect(socket_path(args)) {
+        Ok(mut client) => client.get_state()?,
+        Err(_) => AppliedState::load(state_file(args))?,
+    };
+    Ok(applied.map(|applied| applied.state))
+}
 
-            let payload = DevicePayload {
-                device: CHARACTER_DEVICE_STATIC.to_string(),
-                payload: static_payload.to_vec(),
-            };
+// applies through the daemon when it's running, otherwise writes to the
+// devices directly and records the state itself
+fn apply_state(args: &Args, state: &LightingState) -> Result<Vec<DevicePayload>> {
+    if let Some(mut client) = connect_daemon(args) {
+        return Ok(client.apply(state.clone())?);
+    }
 
-            payloads.push(payload);
-        }
+    let payloads = open_controller(args)?.apply(state)?;
+    if !(args.dry_run || args.output_file.is_some()) {
+        AppliedState::new(state.clone(), payloads.clone())
+            .save(state_file(args))
+            .wrap_err("Failed to record the applied state")?;
+    }
+    Ok(payloads)
+}
 
-        if let Self::Real { .. } = self {
-            let device_static = self.lazy_open_static_device()?;
-            for payload in static_payloads {
-                device_static
-                    .write_all(&payload)
-                    .wrap_err("Failed to write static payload")?;
-            }
+fn open_controller(args: &Args) -> Result<KeyboardController> {
+    if args.dry_run {
+        return Ok(KeyboardController::new(true));
+    }
+    if let Some(path) = &args.output_file {
+        return Ok(KeyboardController::with_backend(FileBackend::new(path)));
+    }
+
+    let config = Config::load(Config::default_path())?;
+    let paths = DevicePaths::resolve(args.device.clone(), args.static_device.clone(), &config)?;
+    Ok(KeyboardController::with_backend(CharDevice::with_paths(
+        paths,
+    )))
+}
+
+fn restore(args: &Args) -> Result<()> {
+    let path = state_file(args);
+    let applied = AppliedState::load(&path)?
+        .ok_or_else(|| eyre!("No applied state recorded in {}", path.display()))?;
+
+    let payloads = match connect_daemon(args) {
+        Some(mut client) => client.apply(applied.state.clone())?,
+        None => {
+            let payloads = applied.replay_payloads();
+            open_controller(args)?
+                .write(&payloads)
+                .wrap_err("Failed to restore the lighting")?;
+            payloads
         }
+    };
+
+    preview_state(&applied.state);
+    if args.dry_run {
+        print_payloads(&payloads);
+    }
+    Ok(())
+}
+
+fn install_units(args: &Args, unit_dir: &Path, sleep_dir: &Path) -> Result<()> {
+    let exe = std::env::current_exe().wrap_err("Failed to locate the acer-rgb binary")?;
+    // the units run as root, so they get the paths resolved here rather
+    // than looking them up in root's config and state
+    let config = Config::load(Config::default_path())?;
+    let devices = DevicePaths::resolve(args.device.clone(), args.static_device.clone(), &config)?;
+    let cwd = std::env::current_dir().wrap_err("Failed to resolve relative paths")?;
+    let absolute = |path: PathBuf| cwd.join(path);
+    let devices = DevicePaths {
+        dynamic: absolute(devices.dynamic),
+        static_: absolute(devices.static_),
+    };
+    let state = absolute(state_file(args));
+    let written = write_units(unit_dir, sleep_dir, &exe, &devices, &state)?;
+    for path in written {
+        println!("Wrote {}", path.display());
+    }
+    println!(
+        "Enable the restore unit with: systemctl enable {}",
+        SERVICE_NAME
+    );
+    Ok(())
+}
 
-        let mut dynamic_payload = [0u8; PAYLOAD_SIZE];
-        dynamic_payload[2] = 100; // brightness
-        dynamic_payload[9] = 1;
+fn print_status(args: &Args, json: bool, follow: bool) -> Result<()> {
+    let applied = match Client::connect(socket_path(args)) {
+        Ok(mut client) => client.get_state()?,
+        Err(_) if follow => return Err(eyre!("--follow needs a running daemon")),
+        Err(_) => AppliedState::load(state_file(args))?,
+    };
 
-        let payload = DevicePayload {
-            device: CHARACTER_DEVICE.to_string(),
-            payload: dynamic_payload.to_vec(),
-        };
+    match applied {
+        Some(applied) => print_applied(&applied, json)?,
+        None => println!("Nothing has been applied yet."),
+    }
 
-        if let Self::Real { .. } = self {
-            let device = self.lazy_open_device()?;
-            device
-                .write_all(&dynamic_payload)
-                .wrap_err("Failed to write dynamic payload")?;
+    if follow {
+        for event in Client::connect(socket_path(args))?.subscribe()? {
+            if let Event::Changed(applied) = event? {
+                println!();
+                print_applied(&applied, json)?;
+            }
         }
+    }
+    Ok(())
+}
 
-        payloads.push(payload);
-        Ok(payloads)
+fn print_applied(applied: &AppliedState, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(applied)?);
+        return Ok(());
     }
 
-    fn apply_dynamic(
-        &mut self,
-        mode: LightingMode,
-        speed: Speed,
-        brightness: Brightness,
-        direction: Direction,
-        color: RGB,
-    ) -> Result<Vec<DevicePayload>> {
-        let mut payload = [0u8; PAYLOAD_SIZE];
-        payload[0] = mode as u8;
-        payload[1] = speed.0;
-        payload[2] = brightness.0;
-        payload[3] = if matches!(mode, LightingMode::Wave) {
-            8
-        } else {
-            0
-        };
-        payload[4] = direction as u8;
-        let [r, g, b] = color.to_bytes();
-        payload[5..8].copy_from_slice(&[r, g, b]);
-        payload[9] = 1;
-
-        let device_payload = DevicePayload {
-            device: CHARACTER_DEVICE.to_string(),
-            payload: payload.to_vec(),
-        };
-
-        if let Self::Real { .. } = self {
-            let device = self.lazy_open_device()?;
-            device
-                .write_all(&payload)
-                .wrap_err("Failed to write dynamic payload")?;
+    let state = &applied.state;
+    println!("Last applied {} ago:", format_duration(applied.age()));
+    println!("Mode: {:?}", state.mode);
+    match state.mode {
+        LightingMode::Static => {
+            for (zone, color) in &state.zones {
+                println!("{}: {}", zone, color);
+            }
+        }
+        _ => {
+            println!("Color: {}", state.color);
+            println!("{}", state.speed);
+            println!("Direction: {:?}", state.direction);
         }
+    }
+    println!("{}", state.brightness);
+
+    preview_state(state);
+    Ok(())
+}
 
-        Ok(vec![device_payload])
+fn print_payloads(payloads: &[DevicePayload]) {
+    println!("\nDevice Payloads:");
+    for payload in payloads {
+        println!("{}\n", payload);
     }
 }
 
@@ -403,11 +986,196 @@ fn get_config_dir() -> PathBuf {
         .join("predator/profiles")
 }
 
+// the arguments saved with --save <name>
+fn load_profile(name: &str) -> Result<Args> {
+    let path = get_config_dir().join(format!("{}.json", name));
+    let loaded = serde_json::from_reader(
+        File::open(path).wrap_err_with(|| format!("Failed to load profile '{}'", name))?,
+    )
+    .wrap_err("Failed to parse profile")?;
+    Ok(loaded)
+}
+
 fn main() -> Result<()> {
     color_eyre::install()?;
     let mut args = Args::parse();
 
-    if args.interactive { args = interactive_mode();}
+    match &args.command {
+        Some(Command::Status { json, follow }) => return print_status(&args, *json, *follow),
+        Some(Command::Clock {
+            face,
+            on_color,
+            off_color,
+            at,
+        }) => {
+            let display = ClockDisplay {
+                face: *face,
+                on: *on_color,
+                off: *off_color,
+            };
+            return match at {
+                Some(time) => commands::clock::run(&args, &FixedClock(*time), &display, true),
+                None => commands::clock::run(&args, &LocalClock, &display, false),
+            };
+        }
+        Some(Command::Daemon) => return run_daemon(&args),
+        Some(Command::Effect {
+            effect,
+            fps,
+            duration,
+        }) => return commands::effect::run(&args, effect, *fps, *duration),
+        Some(Command::Monitor {
+            monitor,
+            interval,
+            sysfs_root,
+            procfs_root,
+        }) => return commands::monitor::run(&args, monitor, *interval, sysfs_root, procfs_root),
+        Some(Command::AutoBrightness {
+            sensor,
+            points,
+            min,
+            max,
+            smoothing,
+            interval,
+            sysfs_root,
+        }) => {
+            let sensor = LightSensor::find(sysfs_root, sensor.as_deref())?;
+            let points = match points.is_empty() {
+                true => commands::brightness::DEFAULT_CURVE
+                    .iter()
+                    .map(|point| point.parse())
+                    .collect::<Result<_, _>>()?,
+                false => points.clone(),
+            };
+            let auto = AutoBrightness::new(BrightnessCurve::new(points), *min, *max, *smoothing);
+            return commands::brightness::run(&args, &sensor, auto, *interval);
+        }
+        Some(Command::AutoProfile {
+            interval,
+            sysfs_root,
+            procfs_root,
+        }) => {
+            let rules = SwitchRules::load(Config::default_path())?;
+            if rules.is_empty() {
+                return Err(eyre!(
+                    "No rules in {}, see the README for the rules section",
+                    Config::default_path().display()
+                ));
+            }
+            return commands::profile::run(&args, &rules, *interval, sysfs_root, procfs_root);
+        }
+        Some(Command::Idle {
+            timeout,
+            battery_timeout,
+            dim_to,
+            fade,
+            logind,
+            session,
+            replay,
+            input_dir,
+            sysfs_root,
+        }) => {
+            let mut source: Box<dyn IdleSource> = match (replay, logind) {
+                (Some(replay), _) => Box::new(RecordedInput::open(replay)?),
+                (None, true) => Box::new(LogindIdle::new(session)),
+                (None, false) => Box::new(InputDevices::open(input_dir)?),
+            };
+            let timeouts = IdleTimeouts {
+                ac: *timeout,
+                battery: battery_timeout.unwrap_or(*timeout),
+            };
+            return commands::idle::run(
+                &args,
+                source.as_mut(),
+                timeouts,
+                Brightness::new(*dim_to)?,
+                *fade,
+                sysfs_root,
+            );
+        }
+        Some(Command::Play { file, fps }) => return commands::play::run(&args, file, *fps),
+        Some(Command::Notify {
+            color,
+            zones,
+            times,
+            period,
+        }) => return commands::notify::run(&args, *color, zones, *times, *period),
+        Some(Command::Progress {
+            fill_color,
+            empty_color,
+            direction,
+        }) => return commands::progress::run(&args, *fill_color, *empty_color, *direction),
+        Some(Command::Restore) => return restore(&args),
+        Some(Command::Run {
+            busy_color,
+            success_color,
+            failure_color,
+            times,
+            period,
+            command,
+        }) => {
+            let colors = commands::run::Colors {
+                busy: *busy_color,
+                success: *success_color,
+                failure: *failure_color,
+            };
+            let code = commands::run::run(&args, command, &colors, *times, *period)?;
+            std::process::exit(code);
+        }
+        Some(Command::Timer {
+            duration,
+            pause_length,
+            cycles,
+            color,
+            break_color,
+            end_color,
+            direction,
+            pause,
+            resume,
+        }) => {
+            let action = match (pause, resume) {
+                (true, _) => Some(TimerAction::Pause),
+                (_, true) => Some(TimerAction::Resume),
+                _ => None,
+            };
+            if let Some(action) = action {
+                return commands::timer::send(&args, action);
+            }
+            let schedule = match duration {
+                Some(duration) => Schedule::cycles(
+                    *duration,
+                    *color,
+                    pause_length.map(|length| (length, *break_color)),
+                    *cycles,
+                ),
+                None => unreachable!("clap requires a duration without --pause or --resume"),
+            };
+            return commands::timer::run(&args, &schedule, *end_color, *direction);
+        }
+        Some(Command::Watch {
+            command,
+            file,
+            rules,
+            interval,
+            once,
+        }) => {
+            let source = match (command, file) {
+                (Some(command), _) => commands::watch::Source::Command(command.clone()),
+                (None, Some(file)) => commands::watch::Source::File(file.clone()),
+                (None, None) => unreachable!("clap requires --command or --file"),
+            };
+            return commands::watch::run(&args, &source, rules, *interval, *once);
+        }
+        Some(Command::InstallUnits {
+            unit_dir,
+            sleep_dir,
+        }) => return install_units(&args, unit_dir, sleep_dir),
+        None => {}
+    }
+
+    if args.interactive {
+        args = interactive_mode();
+    }
 
     let (mut red, mut green, mut blue) = (args.red, args.green, args.blue);
 
@@ -439,11 +1207,19 @@ fn main() -> Result<()> {
     }
 
     let args = if let Some(profile) = args.load.as_ref() {
-        let path = config_dir.join(format!("{}.json", profile));
-        serde_json::from_reader(
-            File::open(path).wrap_err_with(|| format!("Failed to load profile '{}'", profile))?,
-        )
-        .wrap_err("Failed to parse profile")?
+        let loaded = load_profile(profile)?;
+
+        // not part of profiles, so keep them across --load
+        Args {
+            output_file: args.output_file,
+            device: args.device,
+            static_device: args.static_device,
+            state_file: args.state_file,
+            socket: args.socket,
+            transition: args.transition,
+            easing: args.easing,
+            ..loaded
+        }
     } else {
         args
     };
@@ -458,35 +1234,59 @@ fn main() -> Result<()> {
         .wrap_err("Failed to save profile")?;
     }
 
-    let mut controller = KeyboardController::new(args.dry_run)?;
     let color = RGB::new(args.red, args.green, args.blue);
     let speed = Speed::new(args.speed)?;
     let brightness = Brightness::new(args.brightness)?;
 
-    let zones = convert_zones(&args.zones)?;
+    let zones = Zone::from_u8s(&args.zones)?;
+    let state = state_from_args(&args)?;
 
     println!("Configuration:");
     println!("Mode: {:?}", args.mode);
     println!("Zones: {:?}", zones);
     println!("Color: {}", color);
+    for (zone, zone_color) in resolve_zone_colors(&zones, &args.zone_color, color)?
+        .iter()
+        .filter(|(_, c)| *c != color)
+    {
+        println!("{} color: {}", zone, zone_color);
+    }
     println!("{}", speed);
     println!("{}", brightness);
     println!("Direction: {:?}", args.direction);
 
-    let payloads = match args.mode {
-        LightingMode::Static => controller.apply_static(&zones, color)?,
-        _ => controller.apply_dynamic(args.mode, speed, brightness, args.direction, color)?,
-    };
-
-    let zones_u8 = Zone::zones_to_u8s(zones);
+    if let Some(duration) = args.transition {
+        commands::transition::run(&args, &state, duration)?;
+    }
+    let payloads = apply_state(&args, &state)?;
 
-    preview_static_mode(zones_u8, red, green, blue);
+    preview_state(&state);
     if args.dry_run {
-        println!("\nDevice Payloads:");
-        for payload in payloads {
-            println!("{}\n", payload);
-        }
+        print_payloads(&payloads);
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_file_applies_leave_the_state_file_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("state.json");
+        let output = dir.path().join("payloads");
+        let args = Args::parse_from([
+            "acer-rgb".as_ref(),
+            "--output-file".as_ref(),
+            output.as_os_str(),
+            "--state-file".as_ref(),
+            state_path.as_os_str(),
+        ]);
+
+        apply_state(&args, &state_from_args(&args).unwrap()).unwrap();
+        assert!(output.exists());
+        assert!(!state_path.exists());
+    }
+}
@@ -0,0 +1,215 @@
+//! Short alerts drawn over whatever the keyboard shows, which is put back
+//! afterwards.
+
+use crate::effects::{Effect, Engine, Frame};
+use crate::error::{Error, Result};
+use crate::keyboard::KeyboardController;
+use crate::state::LightingState;
+use crate::transition::visible_frame;
+use crate::types::{Brightness, LightingMode, Zone, RGB};
+
+use std::fs::File;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// `$XDG_RUNTIME_DIR/acer-rgb-notify.lock`.
+pub fn default_lock_path() -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("acer-rgb-notify.lock")
+}
+
+/// Blinks some zones a number of times.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub color: RGB,
+    pub zones: Vec<Zone>,
+    pub times: u32,
+    /// Length of one blink, lit for the first half.
+    pub period: Duration,
+    /// What the other zones show meanwhile, and every zone once it's over.
+    pub background: Frame,
+}
+
+impl Notification {
+    pub fn new(color: RGB, zones: Vec<Zone>, times: u32, period: Duration) -> Self {
+        Self {
+            color,
+            zones,
+            times,
+            period,
+            background: [RGB::BLACK; 4],
+        }
+    }
+
+    /// Blinks over what `state` shows. Firmware modes can't be drawn over, so
+    /// the other zones stay dark for those.
+    pub fn over(mut self, state: &LightingState) -> Self {
+        if state.mode == LightingMode::Static {
+            self.background = visible_frame(state);
+        }
+        self
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.period * self.times
+    }
+}
+
+impl Effect for Notification {
+    fn frame(&mut self, elapsed: Duration) -> Frame {
+        let mut frame = self.background;
+        if elapsed >= self.duration() || self.period.is_zero() {
+            return frame;
+        }
+        let lit = (elapsed.as_secs_f64() / self.period.as_secs_f64()).fract() < 0.5;
+        for zone in &self.zones {
+            frame[zone.to_u8() as usize - 1] = match lit {
+                true => self.color,
+                false => RGB::BLACK,
+            };
+        }
+        frame
+    }
+}
+
+/// Held while a notification is showing, so concurrent ones wait for each
+/// other instead of drawing over each other.
+pub struct NotifyLock {
+    _file: File,
+}
+
+impl NotifyLock {
+    /// Blocks until no other notification holds the lock at `path`.
+    pub fn acquire(path: &Path) -> Result<Self> {
+        let lock_err = |source| Error::WriteFile {
+            path: path.display().to_string(),
+            source,
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(lock_err)?;
+        }
+        let file = File::create(path).map_err(lock_err)?;
+        // SAFETY: the descriptor belongs to `file`, which is open for the
+        // whole call. the lock is released when `file` is closed
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            return Err(lock_err(std::io::Error::last_os_error()));
+        }
+        Ok(Self { _file: file })
+    }
+}
+
+/// Plays `notification` through `controller`. The colors are already dimmed,
+/// so the frames are written at full brightness.
+///
+/// Putting the previous state back is left to the caller, since it may have
+/// changed while the notification was showing.
+pub fn show(
+    controller: KeyboardController,
+    notification: &mut Notification,
+    fps: u32,
+) -> Result<KeyboardController> {
+    let duration = notification.duration();
+    let mut engine = Engine::new(controller, Brightness::MAX, fps);
+    engine.run(notification, Some(duration))?;
+    Ok(engine.into_controller())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::Recorder;
+    use crate::encoder::{encode, Device};
+    use crate::types::{Direction, Speed, ALL_ZONES};
+
+    const RED: RGB = RGB::new(255, 0, 0);
+    const BLUE: RGB = RGB::new(0, 0, 255);
+
+    fn zone(n: u8) -> Zone {
+        Zone::new(n).unwrap()
+    }
+
+    #[test]
+    fn blinks_the_zones_then_shows_the_background() {
+        let period = Duration::from_millis(100);
+        let mut notification = Notification::new(RED, vec![zone(2)], 2, period);
+        notification.background = [BLUE; 4];
+        assert_eq!(notification.duration(), Duration::from_millis(200));
+
+        let lit = [BLUE, RED, BLUE, BLUE];
+        let dark = [BLUE, RGB::BLACK, BLUE, BLUE];
+        assert_eq!(notification.frame(Duration::ZERO), lit);
+        assert_eq!(notification.frame(Duration::from_millis(60)), dark);
+        assert_eq!(notification.frame(Duration::from_millis(130)), lit);
+        assert_eq!(notification.frame(Duration::from_millis(199)), dark);
+        assert_eq!(notification.frame(Duration::from_millis(200)), [BLUE; 4]);
+    }
+
+    #[test]
+    fn only_static_states_show_through() {
+        let notification = Notification::new(RED, vec![zone(1)], 1, Duration::from_secs(1));
+        let static_ = LightingState::new_static(vec![(zone(3), BLUE)], Brightness::MAX);
+        assert_eq!(
+            notification.clone().over(&static_).background,
+            [RGB::BLACK, RGB::BLACK, BLUE, RGB::BLACK]
+        );
+
+        let wave = LightingState::new_dynamic(
+            LightingMode::Wave,
+            BLUE,
+            Speed::new(5).unwrap(),
+            Brightness::MAX,
+            Direction::LeftToRight,
+        );
+        assert_eq!(notification.over(&wave).background, [RGB::BLACK; 4]);
+    }
+
+    #[test]
+    fn dynamic_modes_come_back_afterwards() {
+        let previous = LightingState::new_dynamic(
+            LightingMode::Breath,
+            BLUE,
+            Speed::new(3).unwrap(),
+            Brightness::MAX,
+            Direction::LeftToRight,
+        );
+        let recorder = Recorder::new();
+        let mut notification =
+            Notification::new(RED, ALL_ZONES.to_vec(), 1, Duration::from_millis(20))
+                .over(&previous);
+
+        let mut controller = show(
+            KeyboardController::with_backend(recorder.clone()),
+            &mut notification,
+            100,
+        )
+        .unwrap();
+        assert!(recorder
+            .payloads()
+            .iter()
+            .any(|p| p.device == Device::Static));
+
+        recorder.clear();
+        controller.apply(&previous).unwrap();
+        assert_eq!(recorder.payloads(), encode(&previous));
+    }
+
+    #[test]
+    fn a_second_notification_waits_for_the_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notify.lock");
+        let first = NotifyLock::acquire(&path).unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let second = path.clone();
+        std::thread::spawn(move || {
+            let _lock = NotifyLock::acquire(&second).unwrap();
+            sender.send(()).unwrap();
+        });
+        assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+
+        drop(first);
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+    }
+}
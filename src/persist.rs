@@ -0,0 +1,134 @@
+use crate::encoder::{encode, DevicePayload};
+use crate::error::{Error, Result};
+use crate::state::LightingState;
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The last state successfully written to the keyboard.
+///
+/// The devices are write-only, so this is the only way to know what the
+/// keyboard is currently showing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedState {
+    #[serde(flatten)]
+    pub state: LightingState,
+    /// The exact payloads that were written, replayed by `restore`.
+    #[serde(default)]
+    pub payloads: Vec<DevicePayload>,
+    /// Seconds since the unix epoch.
+    pub applied_at: u64,
+}
+
+impl AppliedState {
+    pub fn new(state: LightingState, payloads: Vec<DevicePayload>) -> Self {
+        Self {
+            state,
+            payloads,
+            applied_at: now(),
+        }
+    }
+
+    /// The payloads to write to get back to this state. State files written
+    /// before payloads were recorded are encoded again.
+    pub fn replay_payloads(&self) -> Vec<DevicePayload> {
+        match self.payloads.is_empty() {
+            true => encode(&self.state),
+            false => self.payloads.clone(),
+        }
+    }
+
+    /// `$XDG_STATE_HOME/predator/state.json`, or the local data dir where
+    /// there is no state dir.
+    pub fn default_path() -> PathBuf {
+        dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .unwrap_or_default()
+            .join("predator/state.json")
+    }
+
+    /// Reads the state file, `None` if nothing was applied yet.
+    pub fn load(path: impl AsRef<Path>) -> Result<Option<Self>> {
+        let path = path.as_ref();
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(source) => {
+                return Err(Error::ReadFile {
+                    path: path.display().to_string(),
+                    source,
+                })
+            }
+        };
+        serde_json::from_reader(file)
+            .map(Some)
+            .map_err(|source| Error::ParseFile {
+                path: path.display().to_string(),
+                source,
+            })
+    }
+
+    /// Writes the state file, replacing it atomically.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let write_err = |source| Error::WriteFile {
+            path: path.display().to_string(),
+            source,
+        };
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(write_err)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        let json = serde_json::to_vec_pretty(self).expect("state is always serializable");
+        std::fs::write(&tmp, json).map_err(write_err)?;
+        std::fs::rename(&tmp, path).map_err(write_err)
+    }
+
+    /// Seconds since this state was applied.
+    pub fn age(&self) -> u64 {
+        now().saturating_sub(self.applied_at)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Brightness, Zone, RGB};
+
+    fn state() -> LightingState {
+        LightingState::new_static(
+            vec![(Zone::new(2).unwrap(), RGB::new(0, 128, 255))],
+            Brightness::new(70).unwrap(),
+        )
+    }
+
+    #[test]
+    fn saves_and_loads_the_applied_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("predator/state.json");
+        assert!(AppliedState::load(&path).unwrap().is_none());
+
+        let applied = AppliedState::new(state(), encode(&state()));
+        applied.save(&path).unwrap();
+        let loaded = AppliedState::load(&path).unwrap().unwrap();
+        assert_eq!(loaded.state, state());
+        assert_eq!(loaded.payloads, applied.payloads);
+        assert_eq!(loaded.applied_at, applied.applied_at);
+    }
+
+    #[test]
+    fn old_state_files_are_encoded_again() {
+        let applied = AppliedState::new(state(), Vec::new());
+        assert_eq!(applied.replay_payloads(), encode(&state()));
+    }
+}
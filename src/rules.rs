@@ -0,0 +1,349 @@
+//! Rule tables that turn the output of a command or file into lighting.
+//!
+//! ```json
+//! {
+//!   "rules": [
+//!     { "keyword": "success", "color": "#00ff00" },
+//!     { "keyword": "failed", "color": "#ff0000", "mode": "breath", "speed": 7 },
+//!     { "min": 80, "color": "#ff0000" },
+//!     { "min": 50, "max": 80, "color": "#ffff00" },
+//!     { "keyword": "running", "profile": "building" }
+//!   ],
+//!   "default": { "color": "#000000" }
+//! }
+//! ```
+//!
+//! The first rule that matches wins. `keyword` matches a word of the output,
+//! ignoring case. Words are split at anything but letters, digits, `-` and
+//! `_`, so `failed` matches `Build failed.` and `state=failed` too. `min`
+//! (inclusive) and `max` (exclusive) match the first number in it. Without a
+//! match the `default` applies, if there is one.
+
+use crate::error::{Error, Result};
+use crate::state::LightingState;
+use crate::types::{Brightness, Direction, LightingMode, Speed, ALL_ZONES, RGB};
+
+use serde::Deserialize;
+use std::fs::File;
+use std::path::Path;
+
+/// A value read from a command or file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reading {
+    /// The output, trimmed.
+    pub text: String,
+    /// The first number in the output, e.g. `42` in `load: 42%`.
+    pub number: Option<f64>,
+}
+
+impl Reading {
+    pub fn parse(output: &str) -> Self {
+        let text = output.trim().to_string();
+        let number = text
+            .split(|c: char| c.is_whitespace() || c == ':' || c == '=')
+            .map(|word| word.trim_end_matches('%'))
+            .find_map(|word| word.parse::<f64>().ok().filter(|n| n.is_finite()));
+        Self { text, number }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Keyword(String),
+    Range { min: Option<f64>, max: Option<f64> },
+}
+
+impl Condition {
+    pub fn matches(&self, reading: &Reading) -> bool {
+        match self {
+            Condition::Keyword(keyword) => {
+                let keyword = keyword.trim_matches(|c: char| !is_word_char(c));
+                reading
+                    .text
+                    .split(|c: char| !is_word_char(c))
+                    .any(|word| word.eq_ignore_ascii_case(keyword))
+            }
+            Condition::Range { min, max } => reading.number.is_some_and(|number| {
+                min.map_or(true, |min| number >= min) && max.map_or(true, |max| number < max)
+            }),
+        }
+    }
+}
+
+// what keywords and the words they're compared to are made of
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Light every zone in a color, or run a firmware mode in it.
+    Lighting {
+        mode: LightingMode,
+        color: RGB,
+        speed: Speed,
+        brightness: Option<Brightness>,
+        direction: Direction,
+    },
+    /// Apply a saved profile.
+    Profile(String),
+}
+
+impl Action {
+    /// The state to show, `None` for profiles. `brightness` is used unless the
+    /// rule sets its own.
+    pub fn state(&self, brightness: Brightness) -> Option<LightingState> {
+        match self {
+            Action::Lighting {
+                mode: LightingMode::Static,
+                color,
+                brightness: own,
+                ..
+            } => Some(LightingState::new_static(
+                ALL_ZONES.iter().map(|&zone| (zone, *color)).collect(),
+                own.unwrap_or(brightness),
+            )),
+            Action::Lighting {
+                mode,
+                color,
+                speed,
+                brightness: own,
+                direction,
+            } => Some(LightingState::new_dynamic(
+                *mode,
+                *color,
+                *speed,
+                own.unwrap_or(brightness),
+                *direction,
+            )),
+            Action::Profile(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Lighting {
+                mode: LightingMode::Static,
+                color,
+                ..
+            } => write!(f, "{}", color),
+            Action::Lighting { mode, color, .. } => write!(f, "{:?} {}", mode, color),
+            Action::Profile(profile) => write!(f, "profile '{}'", profile),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub condition: Condition,
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "RawRuleSet")]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    default: Option<Action>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRuleSet {
+    rules: Vec<RawRule>,
+    default: Option<RawAction>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRule {
+    keyword: Option<String>,
+    min: Option<f64>,
+    max: Option<f64>,
+    color: Option<String>,
+    mode: Option<LightingMode>,
+    #[serde(default)]
+    speed: Speed,
+    brightness: Option<Brightness>,
+    #[serde(default)]
+    direction: Direction,
+    profile: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawAction {
+    color: Option<String>,
+    mode: Option<LightingMode>,
+    #[serde(default)]
+    speed: Speed,
+    brightness: Option<Brightness>,
+    #[serde(default)]
+    direction: Direction,
+    profile: Option<String>,
+}
+
+impl TryFrom<RawAction> for Action {
+    type Error = String;
+
+    fn try_from(raw: RawAction) -> Result<Self, Self::Error> {
+        match (raw.profile, raw.color) {
+            (Some(_), Some(_)) => Err("a rule can't have both a color and a profile".to_string()),
+            (Some(profile), None) => Ok(Action::Profile(profile)),
+            (None, Some(color)) => Ok(Action::Lighting {
+                mode: raw.mode.unwrap_or_default(),
+                color: color.parse::<RGB>().map_err(|err| err.to_string())?,
+                speed: raw.speed,
+                brightness: raw.brightness,
+                direction: raw.direction,
+            }),
+            (None, None) => Err("a rule needs a color or a profile".to_string()),
+        }
+    }
+}
+
+impl TryFrom<RawRuleSet> for RuleSet {
+    type Error = String;
+
+    fn try_from(raw: RawRuleSet) -> Result<Self, Self::Error> {
+        let rules = raw
+            .rules
+            .into_iter()
+            .map(|rule| {
+                let condition = match (rule.keyword, rule.min, rule.max) {
+                    (Some(keyword), None, None) => Condition::Keyword(keyword),
+                    (None, None, None) => {
+                        return Err("a rule needs a keyword or a min and/or max".to_string())
+                    }
+                    (None, min, max) => Condition::Range { min, max },
+                    (Some(_), _, _) => {
+                        return Err("a rule can't match both a keyword and a range".to_string())
+                    }
+                };
+                let action = RawAction {
+                    color: rule.color,
+                    mode: rule.mode,
+                    speed: rule.speed,
+                    brightness: rule.brightness,
+                    direction: rule.direction,
+                    profile: rule.profile,
+                };
+                Ok(Rule {
+                    condition,
+                    action: action.try_into()?,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self {
+            rules,
+            default: raw.default.map(Action::try_from).transpose()?,
+        })
+    }
+}
+
+impl RuleSet {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|source| Error::ReadFile {
+            path: path.display().to_string(),
+            source,
+        })?;
+        serde_json::from_reader(file).map_err(|source| Error::ParseFile {
+            path: path.display().to_string(),
+            source,
+        })
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// What to show for `reading`: the first matching rule, or the default.
+    pub fn action(&self, reading: &Reading) -> Option<&Action> {
+        self.rules
+            .iter()
+            .find(|rule| rule.condition.matches(reading))
+            .map(|rule| &rule.action)
+            .or(self.default.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keywords_ignore_punctuation_around_words() {
+        let failed = Condition::Keyword("failed".to_string());
+        assert!(failed.matches(&Reading::parse("Build FAILED.")));
+        assert!(failed.matches(&Reading::parse("state=failed")));
+        assert!(failed.matches(&Reading::parse("(failed)")));
+        assert!(!failed.matches(&Reading::parse("unfailedness")));
+
+        let in_progress = Condition::Keyword("in-progress".to_string());
+        assert!(in_progress.matches(&Reading::parse("job: in-progress")));
+    }
+
+    fn rules() -> RuleSet {
+        serde_json::from_str(
+            r##"{
+                "rules": [
+                    { "keyword": "failed", "color": "#ff0000", "mode": "breath", "speed": 7 },
+                    { "min": 80, "color": "#ff0000" },
+                    { "min": 50, "max": 80, "color": "#ffff00", "brightness": 40 },
+                    { "keyword": "running", "profile": "building" }
+                ],
+                "default": { "color": "#000000" }
+            }"##,
+        )
+        .unwrap()
+    }
+
+    fn action(output: &str) -> Action {
+        rules().action(&Reading::parse(output)).unwrap().clone()
+    }
+
+    #[test]
+    fn reads_the_first_number() {
+        assert_eq!(Reading::parse("load: 42%").number, Some(42.0));
+        assert_eq!(Reading::parse("temp=61.5 fan=3000").number, Some(61.5));
+        assert_eq!(Reading::parse("idle").number, None);
+    }
+
+    #[test]
+    fn the_first_matching_rule_wins() {
+        let red = RGB::new(255, 0, 0);
+        assert_eq!(
+            action("3 tests failed"),
+            Action::Lighting {
+                mode: LightingMode::Breath,
+                color: red,
+                speed: Speed::new(7).unwrap(),
+                brightness: None,
+                direction: Direction::default(),
+            }
+        );
+        assert_eq!(action("80").to_string(), "RGB(255, 0, 0)");
+        assert_eq!(action("79.9").to_string(), "RGB(255, 255, 0)");
+        assert_eq!(action("running"), Action::Profile("building".to_string()));
+        assert_eq!(action("10").to_string(), "RGB(0, 0, 0)");
+
+        let state = action("60").state(Brightness::MAX).unwrap();
+        assert_eq!(state.brightness, Brightness::new(40).unwrap());
+        assert_eq!(state.zones.len(), 4);
+    }
+
+    #[test]
+    fn rules_need_exactly_one_condition_and_action() {
+        for json in [
+            r#"{ "rules": [ { "color": "red" } ] }"#,
+            r#"{ "rules": [ { "keyword": "ok", "min": 1, "color": "red" } ] }"#,
+            r#"{ "rules": [ { "keyword": "ok" } ] }"#,
+            r#"{ "rules": [ { "keyword": "ok", "color": "red", "profile": "p" } ] }"#,
+        ] {
+            assert!(serde_json::from_str::<RuleSet>(json).is_err(), "{}", json);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Battery charge and status from the `power_supply` sysfs class.
+
+use super::{numbered_entries, read_file, read_value};
+use crate::error::{Error, Result};
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// What the battery is doing, from its `status` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BatteryStatus {
+    Charging,
+    Discharging,
+    Full,
+    /// Plugged in but held below full, e.g. by a charge limit.
+    NotCharging,
+    Unknown,
+}
+
+impl From<&str> for BatteryStatus {
+    fn from(status: &str) -> Self {
+        match status.trim() {
+            "Charging" => BatteryStatus::Charging,
+            "Discharging" => BatteryStatus::Discharging,
+            "Full" => BatteryStatus::Full,
+            "Not charging" => BatteryStatus::NotCharging,
+            _ => BatteryStatus::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatteryReading {
+    /// Charge in percent.
+    pub capacity: u8,
+    pub status: BatteryStatus,
+}
+
+/// A `BAT<n>` power supply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Battery {
+    /// E.g. `BAT0`.
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Battery {
+    /// Every battery under `root` (usually `/sys`).
+    pub fn discover(root: &Path) -> Result<Vec<Battery>> {
+        let batteries = numbered_entries(&root.join("class/power_supply"), "BAT")?
+            .into_iter()
+            .map(|path| Battery {
+                name: path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                path,
+            })
+            .collect();
+        Ok(batteries)
+    }
+
+    /// The battery called `name`, or the first one if `name` is `None`.
+    pub fn find(root: &Path, name: Option<&str>) -> Result<Battery> {
+        Self::discover(root)?
+            .into_iter()
+            .find(|battery| name.map_or(true, |name| battery.name == name))
+            .ok_or_else(|| {
+                Error::Sensor(format!(
+                    "no battery {}found under {}",
+                    name.map(|name| format!("named '{}' ", name))
+                        .unwrap_or_default(),
+                    root.display()
+                ))
+            })
+    }
+
+    pub fn read(&self) -> Result<BatteryReading> {
+        let capacity: u8 = read_value(&self.path.join("capacity"))?;
+        let status = read_file(&self.path.join("status"))?;
+        Ok(BatteryReading {
+            capacity: capacity.min(100),
+            status: BatteryStatus::from(status.as_str()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::fake_tree;
+
+    #[test]
+    fn reads_the_first_battery() {
+        let root = fake_tree(&[
+            ("class/power_supply/AC/online", "1\n"),
+            ("class/power_supply/BAT1/capacity", "57\n"),
+            ("class/power_supply/BAT1/status", "Not charging\n"),
+            ("class/power_supply/BAT0/capacity", "104\n"),
+            ("class/power_supply/BAT0/status", "Charging\n"),
+        ]);
+
+        let battery = Battery::find(root.path(), None).unwrap();
+        assert_eq!(battery.name, "BAT0");
+        assert_eq!(
+            battery.read().unwrap(),
+            BatteryReading {
+                capacity: 100,
+                status: BatteryStatus::Charging
+            }
+        );
+
+        let battery = Battery::find(root.path(), Some("BAT1")).unwrap();
+        assert_eq!(battery.read().unwrap().status, BatteryStatus::NotCharging);
+        assert!(Battery::find(root.path(), Some("BAT2")).is_err());
+    }
+}
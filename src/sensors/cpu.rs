@@ -0,0 +1,159 @@
+//! CPU load from the time counters in `/proc/stat`.
+
+use super::read_file;
+use crate::error::{Error, Result};
+
+use std::path::{Path, PathBuf};
+
+/// Time a CPU spent since boot, in clock ticks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub busy: u64,
+    pub total: u64,
+}
+
+impl CpuTimes {
+    /// Parses the counters after the `cpu` label of a `/proc/stat` line.
+    fn parse(fields: &str) -> Option<Self> {
+        let ticks: Vec<u64> = fields
+            .split_whitespace()
+            .map(|field| field.parse().ok())
+            .collect::<Option<_>>()?;
+        // user nice system idle iowait irq softirq steal, guest time is
+        // already counted in user and nice
+        let ticks = &ticks[..ticks.len().min(8)];
+        if ticks.len() < 4 {
+            return None;
+        }
+        let idle = ticks[3] + ticks.get(4).copied().unwrap_or(0);
+        let total: u64 = ticks.iter().sum();
+        Some(Self {
+            busy: total - idle,
+            total,
+        })
+    }
+
+    /// The load between `self` and a later snapshot, in percent.
+    pub fn load_until(&self, later: &CpuTimes) -> f64 {
+        let total = later.total.saturating_sub(self.total);
+        match total {
+            0 => 0.0,
+            _ => later.busy.saturating_sub(self.busy) as f64 / total as f64 * 100.0,
+        }
+    }
+}
+
+/// A snapshot of `/proc/stat`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuStat {
+    /// All CPUs together.
+    pub total: CpuTimes,
+    /// One entry per core, in order.
+    pub cores: Vec<CpuTimes>,
+}
+
+impl CpuStat {
+    pub fn parse(contents: &str) -> Option<Self> {
+        let mut stat = CpuStat::default();
+        let mut found = false;
+        for line in contents.lines() {
+            let Some((label, fields)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            match label {
+                "cpu" => {
+                    stat.total = CpuTimes::parse(fields)?;
+                    found = true;
+                }
+                _ if label.starts_with("cpu") => stat.cores.push(CpuTimes::parse(fields)?),
+                _ => {}
+            }
+        }
+        found.then_some(stat)
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = read_file(path)?;
+        Self::parse(&contents).ok_or_else(|| Error::InvalidReading {
+            path: path.display().to_string(),
+            reason: "no cpu lines found".to_string(),
+        })
+    }
+}
+
+/// Load since the previous sample, in percent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpuLoad {
+    pub total: f64,
+    pub cores: Vec<f64>,
+}
+
+/// Turns successive `/proc/stat` snapshots into load percentages.
+#[derive(Debug, Clone)]
+pub struct CpuSampler {
+    path: PathBuf,
+    last: CpuStat,
+}
+
+impl CpuSampler {
+    /// Starts sampling `<root>/stat`, with `root` usually `/proc`.
+    pub fn new(root: &Path) -> Result<Self> {
+        let path = root.join("stat");
+        let last = CpuStat::read(&path)?;
+        Ok(Self { path, last })
+    }
+
+    /// The load since the previous call, or since [`CpuSampler::new`].
+    pub fn sample(&mut self) -> Result<CpuLoad> {
+        let stat = CpuStat::read(&self.path)?;
+        let load = CpuLoad {
+            total: self.last.total.load_until(&stat.total),
+            cores: self
+                .last
+                .cores
+                .iter()
+                .zip(&stat.cores)
+                .map(|(last, now)| last.load_until(now))
+                .collect(),
+        };
+        self.last = stat;
+        Ok(load)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::fake_tree;
+
+    #[test]
+    fn samples_the_load_between_snapshots() {
+        let root = fake_tree(&[(
+            "stat",
+            "cpu  100 0 100 800 0 0 0 0 0 0\n\
+             cpu0 50 0 50 400 0 0 0 0 0 0\n\
+             cpu1 50 0 50 400 0 0 0 0 0 0\n\
+             intr 12345\n",
+        )]);
+        let mut sampler = CpuSampler::new(root.path()).unwrap();
+
+        // cpu0 busy the whole time, cpu1 idle apart from iowait
+        std::fs::write(
+            root.path().join("stat"),
+            "cpu  200 0 100 880 20 0 0 0 0 0\n\
+             cpu0 150 0 50 400 0 0 0 0 0 0\n\
+             cpu1 50 0 50 480 20 0 0 0 0 0\n",
+        )
+        .unwrap();
+        let load = sampler.sample().unwrap();
+        assert_eq!(load.total, 50.0);
+        assert_eq!(load.cores, vec![100.0, 0.0]);
+    }
+
+    #[test]
+    fn stat_without_cpu_lines_is_an_error() {
+        assert_eq!(CpuStat::parse("intr 1\nctxt 2\n"), None);
+        let root = fake_tree(&[("stat", "cpu  1 2\n")]);
+        assert!(CpuSampler::new(root.path()).is_err());
+    }
+}
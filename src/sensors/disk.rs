@@ -0,0 +1,97 @@
+//! Disk throughput from the block device counters in `/proc/diskstats`.
+
+use super::{read_file, ByteCounters, ByteRates};
+use crate::error::{Error, Result};
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+// diskstats counts in 512 byte sectors, whatever the device's own size
+const SECTOR_SIZE: u64 = 512;
+
+/// The read and written bytes of `device` in `/proc/diskstats`, `None` if
+/// it isn't listed.
+pub fn parse_diskstats(contents: &str, device: &str) -> Option<ByteCounters> {
+    contents.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        // major minor name reads merged sectors ms writes merged sectors ...
+        if fields.get(2) != Some(&device) {
+            return None;
+        }
+        let sectors = |index: usize| fields.get(index)?.parse::<u64>().ok();
+        Some(ByteCounters {
+            incoming: sectors(5)? * SECTOR_SIZE,
+            outgoing: sectors(9)? * SECTOR_SIZE,
+        })
+    })
+}
+
+/// Turns successive `/proc/diskstats` snapshots into throughput.
+#[derive(Debug, Clone)]
+pub struct DiskSampler {
+    path: PathBuf,
+    device: String,
+    last: ByteCounters,
+    last_read: Instant,
+}
+
+impl DiskSampler {
+    /// Starts sampling `device`, e.g. `nvme0n1`, in `<root>/diskstats`, with
+    /// `root` usually `/proc`.
+    pub fn new(root: &Path, device: &str) -> Result<Self> {
+        let mut sampler = Self {
+            path: root.join("diskstats"),
+            device: device.to_string(),
+            last: ByteCounters::default(),
+            last_read: Instant::now(),
+        };
+        sampler.last = sampler.read()?;
+        Ok(sampler)
+    }
+
+    pub fn read(&self) -> Result<ByteCounters> {
+        let contents = read_file(&self.path)?;
+        parse_diskstats(&contents, &self.device).ok_or_else(|| {
+            Error::Sensor(format!(
+                "no device '{}' in {}",
+                self.device,
+                self.path.display()
+            ))
+        })
+    }
+
+    /// Read and written bytes per second since the previous call, or since
+    /// [`DiskSampler::new`].
+    pub fn sample(&mut self) -> Result<ByteRates> {
+        let counters = self.read()?;
+        let rates = self.last.rates_until(&counters, self.last_read.elapsed());
+        self.last = counters;
+        self.last_read = Instant::now();
+        Ok(rates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::fake_tree;
+
+    #[test]
+    fn counts_sectors_as_bytes() {
+        let root = fake_tree(&[(
+            "diskstats",
+            "259 0 nvme0n1 1000 0 2000 300 500 0 4000 700 0 900 1000 0 0 0 0\n\
+             259 1 nvme0n1p1 10 0 20 3 5 0 40 7 0 9 10 0 0 0 0\n",
+        )]);
+
+        let disk = DiskSampler::new(root.path(), "nvme0n1").unwrap();
+        assert_eq!(
+            disk.read().unwrap(),
+            ByteCounters {
+                incoming: 2000 * 512,
+                outgoing: 4000 * 512
+            }
+        );
+        assert!(DiskSampler::new(root.path(), "sda").is_err());
+    }
+}
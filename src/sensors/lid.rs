@@ -0,0 +1,89 @@
+//! Whether the lid is open, from the ACPI button in `/proc/acpi/button/lid`.
+
+use super::read_file;
+use crate::error::{Error, Result};
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LidState {
+    Open,
+    Closed,
+}
+
+impl LidState {
+    /// Reads the first lid under `root` (usually `/proc`). Machines without
+    /// one are always open.
+    pub fn read(root: &Path) -> Result<Self> {
+        let dir = root.join("acpi/button/lid");
+        let mut lids: Vec<_> = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .collect(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(LidState::Open),
+            Err(source) => {
+                return Err(Error::ReadFile {
+                    path: dir.display().to_string(),
+                    source,
+                })
+            }
+        };
+        lids.sort();
+        let Some(lid) = lids.first() else {
+            return Ok(LidState::Open);
+        };
+
+        // e.g. "state:      open"
+        let path = lid.join("state");
+        let contents = read_file(&path)?;
+        match contents.split_once(':').map(|(_, state)| state.trim()) {
+            Some("open") => Ok(LidState::Open),
+            Some("closed") => Ok(LidState::Closed),
+            _ => Err(Error::InvalidReading {
+                path: path.display().to_string(),
+                reason: format!("unknown lid state '{}'", contents.trim()),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for LidState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LidState::Open => f.pad("open"),
+            LidState::Closed => f.pad("closed"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::fake_tree;
+
+    #[test]
+    fn reads_the_first_lid() {
+        let root = fake_tree(&[
+            ("acpi/button/lid/LID0/state", "state:      closed\n"),
+            ("acpi/button/lid/LID1/state", "state:      open\n"),
+        ]);
+        assert_eq!(LidState::read(root.path()).unwrap(), LidState::Closed);
+
+        let broken = fake_tree(&[("acpi/button/lid/LID/state", "state: ajar\n")]);
+        assert!(LidState::read(broken.path()).is_err());
+    }
+
+    #[test]
+    fn machines_without_a_lid_are_open() {
+        assert_eq!(
+            LidState::read(fake_tree(&[]).path()).unwrap(),
+            LidState::Open
+        );
+        let empty = fake_tree(&[]);
+        std::fs::create_dir_all(empty.path().join("acpi/button/lid")).unwrap();
+        assert_eq!(LidState::read(empty.path()).unwrap(), LidState::Open);
+    }
+}
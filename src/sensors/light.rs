@@ -0,0 +1,107 @@
+//! Ambient light from IIO illuminance sensors under `/sys/bus/iio/devices`.
+
+use super::{numbered_entries, read_file, read_value};
+use crate::error::{Error, Result};
+
+use std::path::{Path, PathBuf};
+
+/// An `iio:device<n>` that measures illuminance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LightSensor {
+    /// E.g. `iio:device0`.
+    pub id: String,
+    /// The driver's name for it, e.g. `als` or `acpi-als`.
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl LightSensor {
+    /// Every illuminance sensor under `root` (usually `/sys`).
+    pub fn discover(root: &Path) -> Result<Vec<LightSensor>> {
+        let sensors = numbered_entries(&root.join("bus/iio/devices"), "iio:device")?
+            .into_iter()
+            .filter(|path| {
+                path.join("in_illuminance_input").exists()
+                    || path.join("in_illuminance_raw").exists()
+            })
+            .map(|path| {
+                let id = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let name = read_file(&path.join("name"))
+                    .map(|name| name.trim().to_string())
+                    .unwrap_or_else(|_| id.clone());
+                LightSensor { id, name, path }
+            })
+            .collect();
+        Ok(sensors)
+    }
+
+    /// The sensor whose id or name is `name`, or the first one if `name` is
+    /// `None`.
+    pub fn find(root: &Path, name: Option<&str>) -> Result<LightSensor> {
+        Self::discover(root)?
+            .into_iter()
+            .find(|sensor| name.map_or(true, |name| sensor.id == name || sensor.name == name))
+            .ok_or_else(|| {
+                Error::Sensor(format!(
+                    "no light sensor {}found under {}",
+                    name.map(|name| format!("named '{}' ", name))
+                        .unwrap_or_default(),
+                    root.display()
+                ))
+            })
+    }
+
+    /// The illuminance in lux. Sensors without a processed value give a raw
+    /// one, which is corrected with their offset and scale.
+    pub fn read(&self) -> Result<f64> {
+        let input = self.path.join("in_illuminance_input");
+        if input.exists() {
+            return read_value(&input);
+        }
+        let raw: f64 = read_value(&self.path.join("in_illuminance_raw"))?;
+        let attribute = |name: &str, default: f64| {
+            let path = self.path.join(name);
+            match path.exists() {
+                true => read_value(&path),
+                false => Ok(default),
+            }
+        };
+        let offset = attribute("in_illuminance_offset", 0.0)?;
+        let scale = attribute("in_illuminance_scale", 1.0)?;
+        Ok(((raw + offset) * scale).max(0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::fake_tree;
+
+    #[test]
+    fn reads_processed_and_raw_illuminance() {
+        let root = fake_tree(&[
+            ("bus/iio/devices/iio:device0/name", "accel_3d\n"),
+            ("bus/iio/devices/iio:device0/in_accel_x_raw", "12\n"),
+            ("bus/iio/devices/iio:device1/name", "als\n"),
+            (
+                "bus/iio/devices/iio:device1/in_illuminance_input",
+                "250.5\n",
+            ),
+            ("bus/iio/devices/iio:device2/name", "acpi-als\n"),
+            ("bus/iio/devices/iio:device2/in_illuminance_raw", "100\n"),
+            ("bus/iio/devices/iio:device2/in_illuminance_offset", "-20\n"),
+            ("bus/iio/devices/iio:device2/in_illuminance_scale", "0.5\n"),
+        ]);
+
+        let first = LightSensor::find(root.path(), None).unwrap();
+        assert_eq!(first.id, "iio:device1");
+        assert_eq!(first.read().unwrap(), 250.5);
+
+        let raw = LightSensor::find(root.path(), Some("acpi-als")).unwrap();
+        assert_eq!(raw.read().unwrap(), 40.0);
+        assert!(LightSensor::find(root.path(), Some("iio:device0")).is_err());
+    }
+}
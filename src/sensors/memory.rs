@@ -0,0 +1,154 @@
+//! Memory usage from `/proc/meminfo` and stalls from `/proc/pressure/memory`.
+
+use super::read_file;
+use crate::error::{Error, Result};
+
+use std::path::Path;
+
+/// Share of time tasks were stalled, in percent, averaged over 10, 60 and 300
+/// seconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pressure {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+}
+
+impl Pressure {
+    /// Parses the fields after the `some` or `full` label, e.g.
+    /// `avg10=1.53 avg60=0.42 avg300=0.10 total=123456`.
+    fn parse(fields: &str) -> Option<Self> {
+        let mut pressure = Pressure::default();
+        for field in fields.split_whitespace() {
+            let (key, value) = field.split_once('=')?;
+            let value = || value.parse::<f64>().ok();
+            match key {
+                "avg10" => pressure.avg10 = value()?,
+                "avg60" => pressure.avg60 = value()?,
+                "avg300" => pressure.avg300 = value()?,
+                _ => {}
+            }
+        }
+        Some(pressure)
+    }
+}
+
+/// A snapshot of `/proc/pressure/memory`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MemoryPressure {
+    /// Time at least one task was stalled on memory.
+    pub some: Pressure,
+    /// Time every task was stalled at once. Missing on older kernels.
+    pub full: Option<Pressure>,
+}
+
+impl MemoryPressure {
+    pub fn parse(contents: &str) -> Option<Self> {
+        let mut some = None;
+        let mut full = None;
+        for line in contents.lines() {
+            match line.split_once(char::is_whitespace) {
+                Some(("some", fields)) => some = Some(Pressure::parse(fields)?),
+                Some(("full", fields)) => full = Some(Pressure::parse(fields)?),
+                _ => {}
+            }
+        }
+        Some(Self { some: some?, full })
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = read_file(path)?;
+        Self::parse(&contents).ok_or_else(|| Error::InvalidReading {
+            path: path.display().to_string(),
+            reason: "no 'some' line found".to_string(),
+        })
+    }
+}
+
+/// The parts of `/proc/meminfo` that say how full memory is, in kB.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemInfo {
+    pub total: u64,
+    /// What can be handed out without swapping, caches included.
+    pub available: u64,
+}
+
+impl MemInfo {
+    pub fn parse(contents: &str) -> Option<Self> {
+        let field = |name: &str| {
+            contents.lines().find_map(|line| {
+                let value = line.strip_prefix(name)?.strip_prefix(':')?;
+                value.split_whitespace().next()?.parse().ok()
+            })
+        };
+        Some(Self {
+            total: field("MemTotal")?,
+            available: field("MemAvailable")?,
+        })
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = read_file(path)?;
+        Self::parse(&contents).ok_or_else(|| Error::InvalidReading {
+            path: path.display().to_string(),
+            reason: "MemTotal or MemAvailable missing".to_string(),
+        })
+    }
+
+    /// How much of the memory is in use, in percent.
+    pub fn used_percent(&self) -> f64 {
+        match self.total {
+            0 => 0.0,
+            total => total.saturating_sub(self.available) as f64 / total as f64 * 100.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::fake_tree;
+
+    #[test]
+    fn reads_pressure_with_and_without_full() {
+        let root = fake_tree(&[
+            (
+                "pressure/memory",
+                "some avg10=12.50 avg60=3.00 avg300=0.75 total=123456\n\
+                 full avg10=4.00 avg60=1.00 avg300=0.25 total=23456\n",
+            ),
+            (
+                "pressure/old",
+                "some avg10=1.00 avg60=0.50 avg300=0.00 total=1\n",
+            ),
+            (
+                "pressure/broken",
+                "full avg10=1.00 avg60=0.50 avg300=0.00 total=1\n",
+            ),
+        ]);
+        let pressure = root.path().join("pressure");
+
+        let memory = MemoryPressure::read(&pressure.join("memory")).unwrap();
+        assert_eq!(memory.some.avg10, 12.5);
+        assert_eq!(memory.full.map(|full| full.avg300), Some(0.25));
+        assert_eq!(
+            MemoryPressure::read(&pressure.join("old")).unwrap().full,
+            None
+        );
+        assert!(MemoryPressure::read(&pressure.join("broken")).is_err());
+    }
+
+    #[test]
+    fn reads_used_memory() {
+        let root = fake_tree(&[(
+            "meminfo",
+            "MemTotal:       16000000 kB\n\
+             MemFree:         1000000 kB\n\
+             MemAvailable:    4000000 kB\n",
+        )]);
+
+        let info = MemInfo::read(&root.path().join("meminfo")).unwrap();
+        assert_eq!(info.used_percent(), 75.0);
+        assert_eq!(MemInfo::parse("MemTotal: 100 kB\n"), None);
+    }
+}
@@ -0,0 +1,151 @@
+//! Readings from sysfs and procfs, for lighting that reacts to the machine.
+//!
+//! Every sensor takes the root its files live under, so it can be pointed at
+//! a fake tree instead of `/sys` or `/proc`.
+
+pub mod battery;
+pub mod cpu;
+pub mod disk;
+pub mod lid;
+pub mod light;
+pub mod memory;
+pub mod net;
+pub mod power;
+pub mod thermal;
+
+use crate::error::{Error, Result};
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+pub const SYSFS_ROOT: &str = "/sys";
+pub const PROCFS_ROOT: &str = "/proc";
+
+/// Byte counters that only go up, like the ones in `/proc/net/dev`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ByteCounters {
+    /// Received or read.
+    pub incoming: u64,
+    /// Sent or written.
+    pub outgoing: u64,
+}
+
+impl ByteCounters {
+    /// The rates between `self` and a snapshot taken `elapsed` later. A
+    /// counter that went back, e.g. because an interface was recreated,
+    /// counts as idle.
+    pub fn rates_until(&self, later: &ByteCounters, elapsed: Duration) -> ByteRates {
+        let seconds = elapsed.as_secs_f64();
+        let rate = |before: u64, after: u64| match seconds > 0.0 {
+            true => after.saturating_sub(before) as f64 / seconds,
+            false => 0.0,
+        };
+        ByteRates {
+            incoming: rate(self.incoming, later.incoming),
+            outgoing: rate(self.outgoing, later.outgoing),
+        }
+    }
+}
+
+impl std::ops::Add for ByteCounters {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            incoming: self.incoming.wrapping_add(other.incoming),
+            outgoing: self.outgoing.wrapping_add(other.outgoing),
+        }
+    }
+}
+
+/// Bytes per second.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ByteRates {
+    pub incoming: f64,
+    pub outgoing: f64,
+}
+
+/// Reads a whole sysfs or procfs file.
+pub(crate) fn read_file(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).map_err(|source| Error::ReadFile {
+        path: path.display().to_string(),
+        source,
+    })
+}
+
+/// Reads a file holding a single value, like `/sys/class/thermal/thermal_zone0/temp`.
+pub(crate) fn read_value<T: FromStr>(path: &Path) -> Result<T> {
+    let contents = read_file(path)?;
+    contents.trim().parse().map_err(|_| Error::InvalidReading {
+        path: path.display().to_string(),
+        reason: format!("'{}' is not a number", contents.trim()),
+    })
+}
+
+/// The entries of `dir` named `<prefix><n>`, sorted by `n`. A missing `dir`
+/// has no entries.
+pub(crate) fn numbered_entries(dir: &Path, prefix: &str) -> Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(source) => {
+            return Err(Error::ReadFile {
+                path: dir.display().to_string(),
+                source,
+            })
+        }
+    };
+
+    let mut numbered: Vec<(u32, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let number = name.to_str()?.strip_prefix(prefix)?.parse().ok()?;
+            Some((number, entry.path()))
+        })
+        .collect();
+    numbered.sort();
+    Ok(numbered.into_iter().map(|(_, path)| path).collect())
+}
+
+/// A temporary root holding `files`, given as paths relative to it and their
+/// contents.
+#[cfg(test)]
+pub(crate) fn fake_tree(files: &[(&str, &str)]) -> tempfile::TempDir {
+    let root = tempfile::tempdir().unwrap();
+    for (path, contents) in files {
+        let path = root.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_ignore_counters_that_went_back() {
+        let before = ByteCounters {
+            incoming: 1000,
+            outgoing: 5000,
+        };
+        let after = ByteCounters {
+            incoming: 3000,
+            outgoing: 10,
+        };
+        assert_eq!(
+            before.rates_until(&after, Duration::from_secs(2)),
+            ByteRates {
+                incoming: 1000.0,
+                outgoing: 0.0
+            }
+        );
+        assert_eq!(
+            before.rates_until(&after, Duration::ZERO),
+            ByteRates::default()
+        );
+    }
+}
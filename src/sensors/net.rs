@@ -0,0 +1,125 @@
+//! Network throughput from the interface counters in `/proc/net/dev`.
+
+use super::{read_file, ByteCounters, ByteRates};
+use crate::error::{Error, Result};
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// The received and sent bytes of every interface in `/proc/net/dev`, in
+/// order.
+pub fn parse_net_dev(contents: &str) -> Option<Vec<(String, ByteCounters)>> {
+    contents
+        .lines()
+        // skips the two header lines, which have no colon
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, fields)| {
+            let fields: Vec<u64> = fields
+                .split_whitespace()
+                .map(|field| field.parse().ok())
+                .collect::<Option<_>>()?;
+            // bytes packets errs drop fifo frame compressed multicast, then
+            // the same for transmit
+            Some((
+                name.trim().to_string(),
+                ByteCounters {
+                    incoming: *fields.first()?,
+                    outgoing: *fields.get(8)?,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Turns successive `/proc/net/dev` snapshots into throughput.
+#[derive(Debug, Clone)]
+pub struct NetSampler {
+    path: PathBuf,
+    iface: Option<String>,
+    last: ByteCounters,
+    last_read: Instant,
+}
+
+impl NetSampler {
+    /// Starts sampling `<root>/net/dev`, with `root` usually `/proc`. Without
+    /// an `iface` every interface but `lo` is added up.
+    pub fn new(root: &Path, iface: Option<&str>) -> Result<Self> {
+        let mut sampler = Self {
+            path: root.join("net/dev"),
+            iface: iface.map(str::to_string),
+            last: ByteCounters::default(),
+            last_read: Instant::now(),
+        };
+        sampler.last = sampler.read()?;
+        Ok(sampler)
+    }
+
+    /// The counters of the interface, or of all of them together.
+    pub fn read(&self) -> Result<ByteCounters> {
+        let contents = read_file(&self.path)?;
+        let ifaces = parse_net_dev(&contents).ok_or_else(|| Error::InvalidReading {
+            path: self.path.display().to_string(),
+            reason: "unexpected interface line".to_string(),
+        })?;
+        match &self.iface {
+            Some(iface) => ifaces
+                .into_iter()
+                .find(|(name, _)| name == iface)
+                .map(|(_, counters)| counters)
+                .ok_or_else(|| {
+                    Error::Sensor(format!(
+                        "no interface '{}' in {}",
+                        iface,
+                        self.path.display()
+                    ))
+                }),
+            None => Ok(ifaces
+                .into_iter()
+                .filter(|(name, _)| name != "lo")
+                .map(|(_, counters)| counters)
+                .fold(ByteCounters::default(), |sum, counters| sum + counters)),
+        }
+    }
+
+    /// Received and sent bytes per second since the previous call, or since
+    /// [`NetSampler::new`].
+    pub fn sample(&mut self) -> Result<ByteRates> {
+        let counters = self.read()?;
+        let rates = self.last.rates_until(&counters, self.last_read.elapsed());
+        self.last = counters;
+        self.last_read = Instant::now();
+        Ok(rates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::fake_tree;
+
+    const NET_DEV: &str = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo:  500000     100    0    0    0     0          0         0   500000     100    0    0    0     0       0          0
+wlp2s0: 1000000    2000    0    0    0     0          0         0    20000     300    0    0    0     0       0          0
+  eth0:    3000      10    0    0    0     0          0         0     4000      10    0    0    0     0       0          0
+";
+
+    #[test]
+    fn adds_up_every_interface_but_loopback() {
+        let root = fake_tree(&[("net/dev", NET_DEV)]);
+
+        let all = NetSampler::new(root.path(), None).unwrap();
+        assert_eq!(
+            all.read().unwrap(),
+            ByteCounters {
+                incoming: 1_003_000,
+                outgoing: 24_000
+            }
+        );
+
+        let lo = NetSampler::new(root.path(), Some("lo")).unwrap();
+        assert_eq!(lo.read().unwrap().incoming, 500_000);
+        assert!(NetSampler::new(root.path(), Some("wlan0")).is_err());
+    }
+}
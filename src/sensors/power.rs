@@ -0,0 +1,129 @@
+//! Whether the machine runs on mains or on battery, from the `power_supply`
+//! sysfs class.
+
+use super::read_file;
+use crate::error::{Error, Result};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PowerSource {
+    Ac,
+    Battery,
+}
+
+impl PowerSource {
+    /// Reads the power source under `root` (usually `/sys`). An adapter that
+    /// is online means AC. Without one, machines with a battery run on it and
+    /// machines without one are taken to be on AC. Supplies with a `Device`
+    /// scope, like the battery of a wireless mouse, don't count.
+    pub fn read(root: &Path) -> Result<Self> {
+        let dir = root.join("class/power_supply");
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(PowerSource::Ac),
+            Err(source) => {
+                return Err(Error::ReadFile {
+                    path: dir.display().to_string(),
+                    source,
+                })
+            }
+        };
+
+        let mut has_battery = false;
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let supply = entry.path();
+            let scope = read_file(&supply.join("scope")).unwrap_or_default();
+            if scope.trim() == "Device" {
+                continue;
+            }
+            let kind = read_file(&supply.join("type")).unwrap_or_default();
+            match kind.trim() {
+                // USB-C chargers show up as USB
+                "Mains" | "USB" => {
+                    let online = read_file(&supply.join("online")).unwrap_or_default();
+                    if online.trim() == "1" {
+                        return Ok(PowerSource::Ac);
+                    }
+                }
+                "Battery" => has_battery = true,
+                _ => {}
+            }
+        }
+        Ok(match has_battery {
+            true => PowerSource::Battery,
+            false => PowerSource::Ac,
+        })
+    }
+}
+
+impl std::fmt::Display for PowerSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PowerSource::Ac => f.pad("AC"),
+            PowerSource::Battery => f.pad("battery"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::fake_tree;
+
+    fn read(files: &[(&str, &str)]) -> PowerSource {
+        PowerSource::read(fake_tree(files).path()).unwrap()
+    }
+
+    fn supply(root: &Path, name: &str, files: &[(&str, &str)]) {
+        let dir = root.join("class/power_supply").join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, contents) in files {
+            std::fs::write(dir.join(file), contents).unwrap();
+        }
+    }
+
+    #[test]
+    fn an_online_adapter_means_ac() {
+        let battery = ("class/power_supply/BAT0/type", "Battery\n");
+        assert_eq!(read(&[battery]), PowerSource::Battery);
+        assert_eq!(
+            read(&[
+                battery,
+                ("class/power_supply/ACAD/type", "Mains\n"),
+                ("class/power_supply/ACAD/online", "0\n"),
+                (
+                    "class/power_supply/ucsi-source-psy-USBC000:001/type",
+                    "USB\n"
+                ),
+                (
+                    "class/power_supply/ucsi-source-psy-USBC000:001/online",
+                    "1\n"
+                ),
+            ]),
+            PowerSource::Ac
+        );
+        // desktops without any supply
+        assert_eq!(read(&[]), PowerSource::Ac);
+    }
+
+    #[test]
+    fn device_batteries_do_not_count() {
+        let root = tempfile::tempdir().unwrap();
+        supply(
+            root.path(),
+            "hidpp_battery_0",
+            &[("type", "Battery\n"), ("scope", "Device\n")],
+        );
+        assert_eq!(PowerSource::read(root.path()).unwrap(), PowerSource::Ac);
+
+        supply(root.path(), "BAT1", &[("type", "Battery\n")]);
+        assert_eq!(
+            PowerSource::read(root.path()).unwrap(),
+            PowerSource::Battery
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Temperatures from the `thermal` and `hwmon` sysfs classes.
+
+use super::{numbered_entries, read_file, read_value};
+use crate::error::{Error, Result};
+
+use std::path::{Path, PathBuf};
+
+// sensor names that are the CPU package on common machines, best first
+const CPU_SENSORS: [&str; 4] = ["x86_pkg_temp", "coretemp", "k10temp", "zenpower"];
+
+/// A single temperature input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TempSensor {
+    /// Where it lives, e.g. `thermal_zone0` or `hwmon2/temp1`.
+    pub id: String,
+    /// What it measures, e.g. `x86_pkg_temp` or `coretemp/Package id 0`.
+    pub name: String,
+    /// The file holding the reading in millidegrees.
+    pub path: PathBuf,
+}
+
+impl TempSensor {
+    /// Every thermal zone under `root` (usually `/sys`), followed by every
+    /// hwmon temperature input.
+    pub fn discover(root: &Path) -> Result<Vec<TempSensor>> {
+        let mut sensors = Vec::new();
+
+        for zone in numbered_entries(&root.join("class/thermal"), "thermal_zone")? {
+            let path = zone.join("temp");
+            if !path.exists() {
+                continue;
+            }
+            let id = file_name(&zone);
+            let name = read_file(&zone.join("type"))
+                .map(|name| name.trim().to_string())
+                .unwrap_or_else(|_| id.clone());
+            sensors.push(TempSensor { id, name, path });
+        }
+
+        for hwmon in numbered_entries(&root.join("class/hwmon"), "hwmon")? {
+            let chip = read_file(&hwmon.join("name"))
+                .map(|name| name.trim().to_string())
+                .unwrap_or_else(|_| file_name(&hwmon));
+            for input in temp_inputs(&hwmon)? {
+                let channel = file_name(&input);
+                let channel = channel.trim_end_matches("_input");
+                let label = read_file(&hwmon.join(format!("{}_label", channel)))
+                    .map(|label| label.trim().to_string())
+                    .unwrap_or_else(|_| channel.to_string());
+                sensors.push(TempSensor {
+                    id: format!("{}/{}", file_name(&hwmon), channel),
+                    name: format!("{}/{}", chip, label),
+                    path: input,
+                });
+            }
+        }
+        Ok(sensors)
+    }
+
+    /// The sensor whose id or name is `name`, or the CPU package sensor if
+    /// `name` is `None`. Without a known CPU sensor the first one found is used.
+    pub fn find(root: &Path, name: Option<&str>) -> Result<TempSensor> {
+        let sensors = Self::discover(root)?;
+        let found = match name {
+            Some(name) => sensors
+                .into_iter()
+                .find(|sensor| sensor.id == name || sensor.name == name),
+            None => {
+                let rank = |sensor: &TempSensor| {
+                    CPU_SENSORS
+                        .iter()
+                        .position(|cpu| sensor.name.split('/').next() == Some(cpu))
+                        .unwrap_or(CPU_SENSORS.len())
+                };
+                sensors.into_iter().min_by_key(rank)
+            }
+        };
+
+        found.ok_or_else(|| match name {
+            Some(name) => Error::Sensor(format!(
+                "no temperature sensor named '{}' under {}",
+                name,
+                root.display()
+            )),
+            None => Error::Sensor(format!(
+                "no temperature sensors found under {}",
+                root.display()
+            )),
+        })
+    }
+
+    /// The temperature in degrees Celsius.
+    pub fn read(&self) -> Result<f64> {
+        let millidegrees: i64 = read_value(&self.path)?;
+        Ok(millidegrees as f64 / 1000.0)
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+// the temp<n>_input files of a hwmon chip, sorted by n
+fn temp_inputs(hwmon: &Path) -> Result<Vec<PathBuf>> {
+    let entries = std::fs::read_dir(hwmon).map_err(|source| Error::ReadFile {
+        path: hwmon.display().to_string(),
+        source,
+    })?;
+    let mut inputs: Vec<(u32, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let channel = name
+                .to_str()?
+                .strip_prefix("temp")?
+                .strip_suffix("_input")?;
+            Some((channel.parse().ok()?, entry.path()))
+        })
+        .collect();
+    inputs.sort();
+    Ok(inputs.into_iter().map(|(_, path)| path).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensors::fake_tree;
+
+    #[test]
+    fn finds_the_cpu_package_sensor() {
+        let root = fake_tree(&[
+            ("class/thermal/thermal_zone0/type", "acpitz\n"),
+            ("class/thermal/thermal_zone0/temp", "27800\n"),
+            ("class/thermal/thermal_zone1/type", "x86_pkg_temp\n"),
+            ("class/thermal/thermal_zone1/temp", "54000\n"),
+            ("class/hwmon/hwmon2/name", "nvme\n"),
+            ("class/hwmon/hwmon2/temp1_input", "38850\n"),
+            ("class/hwmon/hwmon2/temp1_label", "Composite\n"),
+        ]);
+
+        let ids: Vec<_> = TempSensor::discover(root.path())
+            .unwrap()
+            .into_iter()
+            .map(|sensor| sensor.id)
+            .collect();
+        assert_eq!(ids, ["thermal_zone0", "thermal_zone1", "hwmon2/temp1"]);
+
+        let cpu = TempSensor::find(root.path(), None).unwrap();
+        assert_eq!(cpu.name, "x86_pkg_temp");
+        assert_eq!(cpu.read().unwrap(), 54.0);
+
+        let nvme = TempSensor::find(root.path(), Some("nvme/Composite")).unwrap();
+        assert_eq!(nvme.read().unwrap(), 38.85);
+        assert!(TempSensor::find(root.path(), Some("gpu")).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use crate::error::{Error, Result};
+use crate::types::{Brightness, Direction, LightingMode, Speed, Zone, RGB};
+use crate::utils::parse_zone_color;
+
+use serde::{Deserialize, Serialize};
+
+/// Everything needed to describe what the keyboard shows.
+///
+/// Static mode lights each entry of `zones` with its own color. The firmware
+/// modes ignore `zones` and animate `color` across the whole keyboard.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LightingState {
+    pub mode: LightingMode,
+    pub zones: Vec<(Zone, RGB)>,
+    pub color: RGB,
+    pub speed: Speed,
+    pub brightness: Brightness,
+    pub direction: Direction,
+    /// Also dim the static zone colors in software, for firmware that ignores
+    /// the brightness byte.
+    #[serde(default)]
+    pub scale_brightness: bool,
+}
+
+impl LightingState {
+    /// A static state lighting each zone with its own color.
+    pub fn new_static(zones: Vec<(Zone, RGB)>, brightness: Brightness) -> Self {
+        let color = zones.first().map_or(RGB::BLACK, |&(_, color)| color);
+        Self {
+            mode: LightingMode::Static,
+            zones,
+            color,
+            speed: Speed::default(),
+            brightness,
+            direction: Direction::default(),
+            scale_brightness: false,
+        }
+    }
+
+    /// A firmware animation of a single color.
+    pub fn new_dynamic(
+        mode: LightingMode,
+        color: RGB,
+        speed: Speed,
+        brightness: Brightness,
+        direction: Direction,
+    ) -> Self {
+        Self {
+            mode,
+            zones: Vec::new(),
+            color,
+            speed,
+            brightness,
+            direction,
+            scale_brightness: false,
+        }
+    }
+
+    /// The colors written to the static device, dimmed if `scale_brightness` is set.
+    pub fn static_zones(&self) -> Vec<(Zone, RGB)> {
+        match self.scale_brightness {
+            true => self
+                .zones
+                .iter()
+                .map(|&(zone, color)| (zone, color.scale(self.brightness)))
+                .collect(),
+            false => self.zones.clone(),
+        }
+    }
+
+    /// The color shown on `zone`, if it is lit.
+    pub fn zone_color(&self, zone: Zone) -> Option<RGB> {
+        match self.mode {
+            LightingMode::Static => self
+                .zones
+                .iter()
+                .find(|(z, _)| *z == zone)
+                .map(|&(_, color)| color),
+            _ => Some(self.color),
+        }
+    }
+}
+
+/// Pairs every zone with its color.
+///
+/// `zones` get the base `color`, `zone_color` entries in `zone=color` format
+/// overwrite it (and light up zones that weren't selected).
+pub fn resolve_zone_colors(
+    zones: &[Zone],
+    zone_color: &[String],
+    color: RGB,
+) -> Result<Vec<(Zone, RGB)>> {
+    let mut zone_colors: Vec<(Zone, RGB)> = zones.iter().map(|&zone| (zone, color)).collect();
+
+    for input in zone_color {
+        let (zone, (red, green, blue)) =
+            parse_zone_color(input).map_err(|reason| Error::InvalidColor {
+                input: input.clone(),
+                reason,
+            })?;
+        let zone = Zone::new(zone)?;
+        let color = RGB::new(red, green, blue);
+
+        match zone_colors.iter_mut().find(|(z, _)| *z == zone) {
+            Some(entry) => entry.1 = color,
+            None => zone_colors.push((zone, color)),
+        }
+    }
+
+    zone_colors.sort_by_key(|(zone, _)| zone.to_u8());
+    Ok(zone_colors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zone(zone: u8) -> Zone {
+        Zone::new(zone).unwrap()
+    }
+
+    #[test]
+    fn zone_colors_overwrite_the_base_color() {
+        let red = RGB::new(255, 0, 0);
+        let zones = [zone(2), zone(1)];
+        let overrides = ["2=#0000ff".to_string(), "4=0,255,0".to_string()];
+
+        assert_eq!(
+            resolve_zone_colors(&zones, &overrides, red).unwrap(),
+            vec![
+                (zone(1), red),
+                (zone(2), RGB::new(0, 0, 255)),
+                (zone(4), RGB::new(0, 255, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_zone_colors_are_an_error() {
+        let result = resolve_zone_colors(&[zone(1)], &["1=nope".to_string()], RGB::BLACK);
+        assert!(matches!(result, Err(Error::InvalidColor { .. })));
+    }
+
+    #[test]
+    fn static_zones_are_only_scaled_when_asked() {
+        let mut state = LightingState::new_static(
+            vec![(zone(1), RGB::new(200, 100, 51))],
+            Brightness::new(50).unwrap(),
+        );
+        assert_eq!(
+            state.static_zones(),
+            vec![(zone(1), RGB::new(200, 100, 51))]
+        );
+
+        state.scale_brightness = true;
+        assert_eq!(state.static_zones(), vec![(zone(1), RGB::new(100, 50, 25))]);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::backend::DevicePaths;
+use crate::error::{Error, Result};
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+pub const SERVICE_NAME: &str = "acer-rgb-restore.service";
+pub const SLEEP_HOOK_NAME: &str = "acer-rgb";
+pub const SYSTEM_UNIT_DIR: &str = "/etc/systemd/system";
+pub const SYSTEM_SLEEP_DIR: &str = "/usr/lib/systemd/system-sleep";
+
+/// A system unit that restores the lighting once at boot.
+///
+/// The unit runs as root, so the devices and state file are passed
+/// explicitly instead of being looked up in root's config and state.
+pub fn restore_service(exe: &Path, devices: &DevicePaths, state_file: &Path) -> String {
+    format!(
+        "[Unit]
+Description=Restore Acer keyboard RGB lighting
+After=systemd-modules-load.service
+ConditionPathExists={state}
+
+[Service]
+Type=oneshot
+ExecStart=\"{exe}\" --device \"{device}\" --static-device \"{static_device}\" --state-file \"{state}\" restore
+
+[Install]
+WantedBy=multi-user.target
+",
+        exe = exe.display(),
+        device = devices.dynamic.display(),
+        static_device = devices.static_.display(),
+        state = state_file.display(),
+    )
+}
+
+/// A systemd-sleep hook that restores the lighting after resume, with the
+/// same explicit paths as [`restore_service`].
+pub fn sleep_hook(exe: &Path, devices: &DevicePaths, state_file: &Path) -> String {
+    format!(
+        "#!/bin/sh
+# restores the keyboard lighting after suspend, generated by acer-rgb
+case \"$1\" in
+    post) exec {exe} --device {device} --static-device {static_device} --state-file {state} restore ;;
+esac
+",
+        exe = shell_quote(exe),
+        device = shell_quote(&devices.dynamic),
+        static_device = shell_quote(&devices.static_),
+        state = shell_quote(state_file),
+    )
+}
+
+/// Writes the restore unit to `unit_dir` and the sleep hook to `sleep_dir`,
+/// returning the paths that were written.
+pub fn write_units(
+    unit_dir: &Path,
+    sleep_dir: &Path,
+    exe: &Path,
+    devices: &DevicePaths,
+    state_file: &Path,
+) -> Result<Vec<PathBuf>> {
+    let service = unit_dir.join(SERVICE_NAME);
+    write_file(&service, &restore_service(exe, devices, state_file), 0o644)?;
+
+    let hook = sleep_dir.join(SLEEP_HOOK_NAME);
+    write_file(&hook, &sleep_hook(exe, devices, state_file), 0o755)?;
+
+    Ok(vec![service, hook])
+}
+
+fn write_file(path: &Path, contents: &str, mode: u32) -> Result<()> {
+    let write_err = |source| Error::WriteFile {
+        path: path.display().to_string(),
+        source,
+    };
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(write_err)?;
+    }
+    std::fs::write(path, contents).map_err(write_err)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).map_err(write_err)
+}
+
+fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn devices() -> DevicePaths {
+        DevicePaths {
+            dynamic: PathBuf::from("/dev/acer-gkbbl-1"),
+            static_: PathBuf::from("/dev/acer-gkbbl-static-1"),
+        }
+    }
+
+    #[test]
+    fn units_name_every_path_explicitly() {
+        let exe = Path::new("/usr/bin/acer-rgb");
+        let state = Path::new("/home/me/.local/state/predator/state.json");
+
+        let service = restore_service(exe, &devices(), state);
+        assert!(service.contains(
+            "ExecStart=\"/usr/bin/acer-rgb\" --device \"/dev/acer-gkbbl-1\" \
+             --static-device \"/dev/acer-gkbbl-static-1\" \
+             --state-file \"/home/me/.local/state/predator/state.json\" restore"
+        ));
+
+        let hook = sleep_hook(exe, &devices(), state);
+        assert!(hook.contains(
+            "exec '/usr/bin/acer-rgb' --device '/dev/acer-gkbbl-1' \
+             --static-device '/dev/acer-gkbbl-static-1' \
+             --state-file '/home/me/.local/state/predator/state.json' restore"
+        ));
+    }
+}
@@ -0,0 +1,264 @@
+//! Animations described as data: per-zone colors at points in time, blended
+//! into each other.
+//!
+//! ```json
+//! {
+//!   "duration": "4s",
+//!   "loops": 3,
+//!   "keyframes": [
+//!     { "time": "0s", "color": "#ff0000" },
+//!     { "time": "1s", "zones": { "1": "#00ff00", "4": "#0000ff" }, "easing": "ease-in-out" },
+//!     { "time": "4s", "color": "#ff0000" }
+//!   ]
+//! }
+//! ```
+//!
+//! `color` sets every zone, `zones` only the listed ones, the others keep
+//! their color from the previous keyframe. `easing` shapes the blend from the
+//! previous keyframe into this one.
+
+use crate::easing::Easing;
+use crate::effects::{Effect, Frame};
+use crate::error::{Error, Result};
+use crate::types::{Zone, RGB};
+use crate::utils::parse_duration;
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframe {
+    pub time: Duration,
+    pub colors: Frame,
+    pub easing: Easing,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "RawTimeline")]
+pub struct Timeline {
+    keyframes: Vec<Keyframe>,
+    duration: Duration,
+    /// How often to play the timeline, 0 loops forever.
+    loops: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawTimeline {
+    duration: Option<String>,
+    #[serde(default = "default_loops")]
+    loops: u32,
+    keyframes: Vec<RawKeyframe>,
+}
+
+fn default_loops() -> u32 {
+    1
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawKeyframe {
+    time: String,
+    color: Option<String>,
+    #[serde(default)]
+    zones: BTreeMap<u8, String>,
+    #[serde(default)]
+    easing: Easing,
+}
+
+fn parse_rgb(input: &str) -> Result<RGB, String> {
+    input.parse::<RGB>().map_err(|err| err.to_string())
+}
+
+impl TryFrom<RawTimeline> for Timeline {
+    type Error = String;
+
+    fn try_from(raw: RawTimeline) -> Result<Self, Self::Error> {
+        let mut keyframes: Vec<Keyframe> = Vec::new();
+        let mut colors = [RGB::BLACK; 4];
+
+        for raw_keyframe in raw.keyframes {
+            let time = parse_duration(&raw_keyframe.time)?;
+            if let Some(color) = &raw_keyframe.color {
+                colors = [parse_rgb(color)?; 4];
+            }
+            for (zone, color) in &raw_keyframe.zones {
+                let zone = Zone::new(*zone).map_err(|err| err.to_string())?;
+                colors[zone.to_u8() as usize - 1] = parse_rgb(color)?;
+            }
+            if keyframes.last().is_some_and(|last| last.time > time) {
+                return Err(format!(
+                    "keyframe at {} comes before the one above it",
+                    raw_keyframe.time
+                ));
+            }
+            keyframes.push(Keyframe {
+                time,
+                colors,
+                easing: raw_keyframe.easing,
+            });
+        }
+
+        let last = keyframes
+            .last()
+            .ok_or_else(|| "a timeline needs at least one keyframe".to_string())?
+            .time;
+        let duration = match raw.duration {
+            Some(duration) => parse_duration(&duration)?,
+            None => last,
+        };
+        if duration < last {
+            return Err("the duration is shorter than the last keyframe".to_string());
+        }
+        if duration.checked_mul(raw.loops).is_none() {
+            return Err(format!(
+                "{} loops of {:?} are too long",
+                raw.loops, duration
+            ));
+        }
+
+        Ok(Self {
+            keyframes,
+            duration,
+            loops: raw.loops,
+        })
+    }
+}
+
+impl Timeline {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|source| Error::ReadFile {
+            path: path.display().to_string(),
+            source,
+        })?;
+        serde_json::from_reader(file).map_err(|source| Error::ParseFile {
+            path: path.display().to_string(),
+            source,
+        })
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// Length of a single loop.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Length of the whole playback, `None` if it loops forever.
+    pub fn total_duration(&self) -> Option<Duration> {
+        match self.loops {
+            0 => None,
+            loops => self.duration.checked_mul(loops),
+        }
+    }
+
+    /// The colors `offset` into a single loop.
+    pub fn sample(&self, offset: Duration) -> Frame {
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= offset);
+        match (next.checked_sub(1), self.keyframes.get(next)) {
+            (None, _) => self.keyframes[0].colors,
+            (Some(previous), None) => self.keyframes[previous].colors,
+            (Some(previous), Some(to)) => {
+                let from = &self.keyframes[previous];
+                let span = (to.time - from.time).as_secs_f64();
+                let t = to.easing.apply((offset - from.time).as_secs_f64() / span);
+                std::array::from_fn(|zone| from.colors[zone].lerp(to.colors[zone], t))
+            }
+        }
+    }
+}
+
+impl Effect for Timeline {
+    fn frame(&mut self, elapsed: Duration) -> Frame {
+        if self.duration.is_zero() {
+            return self.sample(Duration::ZERO);
+        }
+        // hold the very last frame once every loop has played
+        if self.total_duration().is_some_and(|total| elapsed >= total) {
+            return self.sample(self.duration);
+        }
+        let offset = elapsed.as_secs_f64() % self.duration.as_secs_f64();
+        self.sample(Duration::from_secs_f64(offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeline(json: &str) -> Timeline {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn samples_blend_between_keyframes() {
+        let timeline = timeline(
+            r##"{
+                "duration": "4s",
+                "keyframes": [
+                    { "time": "1s", "color": "#000000" },
+                    { "time": "3s", "zones": { "2": "#c86420" } }
+                ]
+            }"##,
+        );
+        let black = RGB::BLACK;
+
+        // held before the first keyframe and after the last
+        assert_eq!(timeline.sample(Duration::ZERO), [black; 4]);
+        assert_eq!(
+            timeline.sample(Duration::from_secs(4)),
+            [black, RGB::new(200, 100, 32), black, black]
+        );
+        // zones the keyframe doesn't list keep their color
+        assert_eq!(
+            timeline.sample(Duration::from_secs(2)),
+            [black, RGB::new(100, 50, 16), black, black]
+        );
+    }
+
+    #[test]
+    fn loops_then_holds_the_last_frame() {
+        let mut timeline = timeline(
+            r##"{
+                "loops": 2,
+                "keyframes": [
+                    { "time": "0s", "color": "#000000" },
+                    { "time": "2s", "color": "#ffffff" }
+                ]
+            }"##,
+        );
+        let gray = RGB::new(128, 128, 128);
+
+        assert_eq!(timeline.total_duration(), Some(Duration::from_secs(4)));
+        assert_eq!(timeline.frame(Duration::from_secs(1)), [gray; 4]);
+        assert_eq!(timeline.frame(Duration::from_secs(3)), [gray; 4]);
+        assert_eq!(
+            timeline.frame(Duration::from_secs(9)),
+            [RGB::new(255, 255, 255); 4]
+        );
+    }
+
+    #[test]
+    fn keyframes_have_to_be_in_order() {
+        let result = serde_json::from_str::<Timeline>(
+            r##"{ "keyframes": [ { "time": "2s", "color": "red" }, { "time": "1s", "color": "blue" } ] }"##,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn too_many_loops_are_an_error() {
+        let result = serde_json::from_str::<Timeline>(
+            r##"{ "loops": 4294967295, "duration": "10000000000h", "keyframes": [ { "time": "0s", "color": "red" } ] }"##,
+        );
+        assert!(result.unwrap_err().to_string().contains("too long"));
+    }
+}
@@ -0,0 +1,164 @@
+//! Countdown timers that drain the zones, with optional work/break cycles.
+
+use crate::effects::Frame;
+use crate::gauge::bar;
+use crate::types::{Direction, RGB};
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How much of a phase is left when its color starts shifting to the end
+/// color.
+pub const WARN_FRACTION: f64 = 0.2;
+
+/// Sent through the daemon to a running timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimerAction {
+    Pause,
+    Resume,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhaseKind {
+    Work,
+    Break,
+}
+
+impl std::fmt::Display for PhaseKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PhaseKind::Work => f.pad("work"),
+            PhaseKind::Break => f.pad("break"),
+        }
+    }
+}
+
+/// One countdown of a [`Schedule`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Phase {
+    pub kind: PhaseKind,
+    pub duration: Duration,
+    pub color: RGB,
+}
+
+impl Phase {
+    /// The zones `elapsed` into the phase: a bar of what's left, shifting to
+    /// `end_color` over the last [`WARN_FRACTION`].
+    pub fn frame(&self, elapsed: Duration, end_color: RGB, direction: Direction) -> Frame {
+        let remaining = match self.duration.is_zero() {
+            true => 0.0,
+            false => 1.0 - elapsed.as_secs_f64() / self.duration.as_secs_f64(),
+        }
+        .clamp(0.0, 1.0);
+        let color = end_color.lerp(self.color, remaining / WARN_FRACTION);
+        bar(remaining, color, RGB::BLACK, direction)
+    }
+
+    /// How far into the phase the color starts shifting.
+    pub fn warn_at(&self) -> Duration {
+        self.duration.mul_f64(1.0 - WARN_FRACTION)
+    }
+}
+
+/// Work phases, each followed by a break except the last one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    phases: Vec<Phase>,
+}
+
+impl Schedule {
+    /// `cycles` work phases with a break of `pause` between each. Without a
+    /// break the work phases follow each other directly.
+    pub fn cycles(work: Duration, color: RGB, pause: Option<(Duration, RGB)>, cycles: u32) -> Self {
+        let mut phases = Vec::new();
+        for cycle in 0..cycles.max(1) {
+            if cycle > 0 {
+                if let Some((duration, color)) = pause {
+                    phases.push(Phase {
+                        kind: PhaseKind::Break,
+                        duration,
+                        color,
+                    });
+                }
+            }
+            phases.push(Phase {
+                kind: PhaseKind::Work,
+                duration: work,
+                color,
+            });
+        }
+        Self { phases }
+    }
+
+    pub fn phases(&self) -> &[Phase] {
+        &self.phases
+    }
+
+    /// Running time of every phase together, without pauses.
+    pub fn total(&self) -> Duration {
+        self.phases.iter().map(|phase| phase.duration).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GREEN: RGB = RGB::new(0, 255, 0);
+    const BLUE: RGB = RGB::new(0, 0, 255);
+    const RED: RGB = RGB::new(255, 0, 0);
+
+    fn minutes(minutes: u64) -> Duration {
+        Duration::from_secs(minutes * 60)
+    }
+
+    #[test]
+    fn breaks_go_between_work_phases() {
+        let schedule = Schedule::cycles(minutes(25), GREEN, Some((minutes(5), BLUE)), 3);
+        let kinds: Vec<_> = schedule.phases().iter().map(|phase| phase.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                PhaseKind::Work,
+                PhaseKind::Break,
+                PhaseKind::Work,
+                PhaseKind::Break,
+                PhaseKind::Work
+            ]
+        );
+        assert_eq!(schedule.total(), minutes(85));
+
+        let single = Schedule::cycles(minutes(25), GREEN, None, 0);
+        assert_eq!(single.phases().len(), 1);
+    }
+
+    #[test]
+    fn phases_drain_and_shift_to_the_end_color() {
+        let phase = Phase {
+            kind: PhaseKind::Work,
+            duration: minutes(10),
+            color: GREEN,
+        };
+        let half = GREEN.lerp(RED, 0.5);
+
+        assert_eq!(
+            phase.frame(Duration::ZERO, RED, Direction::LeftToRight),
+            [GREEN; 4]
+        );
+        assert_eq!(
+            phase.frame(minutes(5), RED, Direction::LeftToRight),
+            [GREEN, GREEN, RGB::BLACK, RGB::BLACK]
+        );
+        // a tenth left is halfway into the warning
+        assert_eq!(
+            phase.frame(minutes(9), RED, Direction::RightToLeft),
+            [RGB::BLACK, RGB::BLACK, RGB::BLACK, half.dim(0.4)]
+        );
+        assert_eq!(
+            phase.frame(minutes(11), RED, Direction::LeftToRight),
+            [RGB::BLACK; 4]
+        );
+        assert_eq!(phase.warn_at(), minutes(8));
+    }
+}
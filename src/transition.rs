@@ -0,0 +1,128 @@
+//! Smooth fades from the last applied state to a new one.
+
+use crate::easing::Easing;
+use crate::effects::{Effect, Engine, Frame};
+use crate::error::Result;
+use crate::keyboard::KeyboardController;
+use crate::state::LightingState;
+use crate::types::{Brightness, LightingMode, ALL_ZONES, RGB};
+
+use std::time::Duration;
+
+/// Blends every zone from one frame to another.
+#[derive(Debug, Clone)]
+pub struct Crossfade {
+    pub from: Frame,
+    pub to: Frame,
+    pub duration: Duration,
+    pub easing: Easing,
+}
+
+impl Effect for Crossfade {
+    fn frame(&mut self, elapsed: Duration) -> Frame {
+        let t = match self.duration.is_zero() {
+            true => 1.0,
+            false => elapsed.as_secs_f64() / self.duration.as_secs_f64(),
+        };
+        let t = self.easing.apply(t);
+        std::array::from_fn(|zone| self.from[zone].lerp(self.to[zone], t))
+    }
+}
+
+/// What `state` looks like on each zone, dimmed to its brightness. Zones that
+/// aren't lit are black.
+pub fn visible_frame(state: &LightingState) -> Frame {
+    ALL_ZONES.map(|zone| {
+        state
+            .zone_color(zone)
+            .map_or(RGB::BLACK, |color| color.scale(state.brightness))
+    })
+}
+
+impl Crossfade {
+    /// Fades from what `from` shows to `to`. Firmware modes can't be blended
+    /// into, so a dynamic `to` is reached by fading to black first.
+    pub fn between(
+        from: &LightingState,
+        to: &LightingState,
+        duration: Duration,
+        easing: Easing,
+    ) -> Self {
+        let target = match to.mode {
+            LightingMode::Static => visible_frame(to),
+            _ => [RGB::BLACK; 4],
+        };
+        Self {
+            from: visible_frame(from),
+            to: target,
+            duration,
+            easing,
+        }
+    }
+}
+
+/// Plays the fade from `from` to `to` through `controller`. The caller still
+/// has to apply `to` afterwards.
+///
+/// The colors are already dimmed, so the frames are written at full brightness.
+pub fn crossfade(
+    controller: KeyboardController,
+    from: &LightingState,
+    to: &LightingState,
+    duration: Duration,
+    easing: Easing,
+    fps: u32,
+) -> Result<KeyboardController> {
+    let mut fade = Crossfade::between(from, to, duration, easing);
+    let mut engine = Engine::new(controller, Brightness::MAX, fps);
+    engine.run(&mut fade, Some(duration))?;
+    Ok(engine.into_controller())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Direction, Speed, Zone};
+
+    const RED: RGB = RGB::new(255, 0, 0);
+
+    #[test]
+    fn fades_between_what_the_states_show() {
+        let from = LightingState::new_static(vec![(Zone::new(1).unwrap(), RED)], Brightness::MAX);
+        let to = LightingState::new_static(
+            ALL_ZONES.iter().map(|&zone| (zone, RED)).collect(),
+            Brightness::new(50).unwrap(),
+        );
+        let mut fade = Crossfade::between(&from, &to, Duration::from_secs(1), Easing::Linear);
+
+        assert_eq!(
+            fade.frame(Duration::ZERO),
+            [RED, RGB::BLACK, RGB::BLACK, RGB::BLACK]
+        );
+        let half = RGB::new(191, 0, 0);
+        assert_eq!(
+            fade.frame(Duration::from_millis(500)),
+            [
+                half,
+                RGB::new(64, 0, 0),
+                RGB::new(64, 0, 0),
+                RGB::new(64, 0, 0)
+            ]
+        );
+        assert_eq!(fade.frame(Duration::from_secs(2)), [RGB::new(127, 0, 0); 4]);
+    }
+
+    #[test]
+    fn firmware_modes_are_faded_into_from_black() {
+        let from = LightingState::new_static(vec![(Zone::new(1).unwrap(), RED)], Brightness::MAX);
+        let wave = LightingState::new_dynamic(
+            LightingMode::Wave,
+            RED,
+            Speed::default(),
+            Brightness::MAX,
+            Direction::default(),
+        );
+        let fade = Crossfade::between(&from, &wave, Duration::ZERO, Easing::Linear);
+        assert_eq!(fade.to, [RGB::BLACK; 4]);
+    }
+}
@@ -0,0 +1,306 @@
+use crate::error::{Error, Result};
+use crate::utils::scale_color;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Every zone of the keyboard, left to right.
+pub const ALL_ZONES: [Zone; 4] = [Zone(1), Zone(2), Zone(3), Zone(4)];
+
+/// Lighting modes supported by the keyboard firmware.
+///
+/// The discriminant is the mode byte sent to the device.
+#[derive(Debug, Clone, Copy, Default, ValueEnum, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LightingMode {
+    #[default]
+    Static,
+    Breath,
+    Neon,
+    Wave,
+    Shifting,
+    Zoom,
+}
+
+impl FromStr for LightingMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "static" => Ok(LightingMode::Static),
+            "breath" => Ok(LightingMode::Breath),
+            "neon" => Ok(LightingMode::Neon),
+            "wave" => Ok(LightingMode::Wave),
+            "shifting" => Ok(LightingMode::Shifting),
+            "zoom" => Ok(LightingMode::Zoom),
+            _ => Err(format!("'{}' is not a valid lighting mode", s)),
+        }
+    }
+}
+
+/// Direction of the animated firmware modes.
+#[derive(Debug, Clone, Copy, Default, ValueEnum, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Direction {
+    RightToLeft = 1,
+    #[default]
+    LeftToRight = 2,
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "right-to-left" => Ok(Direction::RightToLeft),
+            "left-to-right" => Ok(Direction::LeftToRight),
+            "rl" => Ok(Direction::RightToLeft),
+            "lr" => Ok(Direction::LeftToRight),
+            _ => Err(format!("'{}' is not a valid direction", s)),
+        }
+    }
+}
+
+/// A 24-bit color.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RGB {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl RGB {
+    pub const BLACK: Self = Self::new(0, 0, 0);
+
+    pub const fn new(red: u8, green: u8, blue: u8) -> Self {
+        Self { red, green, blue }
+    }
+
+    pub fn to_bytes(self) -> [u8; 3] {
+        [self.red, self.green, self.blue]
+    }
+
+    /// Dims the color in software, for firmware that ignores the brightness byte.
+    pub fn scale(self, brightness: Brightness) -> Self {
+        let (red, green, blue) = scale_color((self.red, self.green, self.blue), brightness.0);
+        Self::new(red, green, blue)
+    }
+
+    /// Multiplies each channel by `factor`, clamped to 0.0 - 1.0.
+    pub fn dim(self, factor: f64) -> Self {
+        let factor = factor.clamp(0.0, 1.0);
+        let dim = |c: u8| (c as f64 * factor).round() as u8;
+        Self::new(dim(self.red), dim(self.green), dim(self.blue))
+    }
+
+    /// Linear interpolation, `t` = 0.0 gives `self` and 1.0 gives `other`.
+    pub fn lerp(self, other: Self, t: f64) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Self::new(
+            lerp(self.red, other.red),
+            lerp(self.green, other.green),
+            lerp(self.blue, other.blue),
+        )
+    }
+
+    /// Converts a hue in degrees plus saturation and value (0.0 - 1.0).
+    pub fn from_hsv(hue: f64, saturation: f64, value: f64) -> Self {
+        let hue = hue.rem_euclid(360.0) / 60.0;
+        let chroma = value * saturation;
+        let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+        let (r, g, b) = match hue as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = value - chroma;
+        let channel = |c: f64| ((c + m) * 255.0).round() as u8;
+        Self::new(channel(r), channel(g), channel(b))
+    }
+}
+
+impl FromStr for RGB {
+    type Err = Error;
+
+    /// Parses `#rrggbb`, `#rgb`, `rrggbb`, `r,g,b` or a name like `red`.
+    fn from_str(s: &str) -> Result<Self> {
+        crate::utils::parse_color(s)
+            .map(|(red, green, blue)| Self::new(red, green, blue))
+            .map_err(|reason| Error::InvalidColor {
+                input: s.to_string(),
+                reason,
+            })
+    }
+}
+
+impl std::fmt::Display for RGB {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RGB({}, {}, {})", self.red, self.green, self.blue)
+    }
+}
+
+/// One of the four keyboard zones, numbered 1 to 4 from left to right.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "u8", into = "u8")]
+pub struct Zone(u8);
+
+impl Zone {
+    pub fn new(zone: u8) -> Result<Self> {
+        match zone {
+            1..=4 => Ok(Self(zone)),
+            _ => Err(Error::InvalidZone(zone)),
+        }
+    }
+
+    /// Converts a zone list as given on the command line, where 0 selects every zone.
+    pub fn from_u8s(zones: &[u8]) -> Result<Vec<Self>> {
+        if zones.contains(&0) {
+            return Ok(ALL_ZONES.to_vec());
+        }
+        zones.iter().map(|&z| Self::new(z)).collect()
+    }
+
+    /// The bit selecting this zone in a static payload.
+    pub fn to_mask(self) -> u8 {
+        1 << (self.0 - 1)
+    }
+
+    pub fn to_u8(self) -> u8 {
+        self.0
+    }
+}
+
+impl TryFrom<u8> for Zone {
+    type Error = Error;
+
+    fn try_from(zone: u8) -> Result<Self> {
+        Self::new(zone)
+    }
+}
+
+impl From<Zone> for u8 {
+    fn from(zone: Zone) -> Self {
+        zone.0
+    }
+}
+
+impl std::fmt::Display for Zone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Zone {}", self.0)
+    }
+}
+
+/// Animation speed of the firmware modes, 0 to 9.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(try_from = "u8", into = "u8")]
+pub struct Speed(u8);
+
+impl Speed {
+    pub fn new(speed: u8) -> Result<Self> {
+        match speed <= 9 {
+            true => Ok(Self(speed)),
+            false => Err(Error::InvalidSpeed(speed)),
+        }
+    }
+
+    pub fn get(self) -> u8 {
+        self.0
+    }
+}
+
+impl Default for Speed {
+    fn default() -> Self {
+        Self(4)
+    }
+}
+
+impl TryFrom<u8> for Speed {
+    type Error = Error;
+
+    fn try_from(speed: u8) -> Result<Self> {
+        Self::new(speed)
+    }
+}
+
+impl From<Speed> for u8 {
+    fn from(speed: Speed) -> Self {
+        speed.0
+    }
+}
+
+impl std::fmt::Display for Speed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Speed {}", self.0)
+    }
+}
+
+/// Brightness percentage, 0 to 100.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "u8", into = "u8")]
+pub struct Brightness(u8);
+
+impl Brightness {
+    pub const MAX: Self = Self(100);
+
+    pub fn new(brightness: u8) -> Result<Self> {
+        match brightness <= 100 {
+            true => Ok(Self(brightness)),
+            false => Err(Error::InvalidBrightness(brightness)),
+        }
+    }
+
+    pub fn get(self) -> u8 {
+        self.0
+    }
+}
+
+impl Default for Brightness {
+    fn default() -> Self {
+        Self::MAX
+    }
+}
+
+impl TryFrom<u8> for Brightness {
+    type Error = Error;
+
+    fn try_from(brightness: u8) -> Result<Self> {
+        Self::new(brightness)
+    }
+}
+
+impl From<Brightness> for u8 {
+    fn from(brightness: Brightness) -> Self {
+        brightness.0
+    }
+}
+
+impl std::fmt::Display for Brightness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Brightness {}%", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_selects_every_zone() {
+        assert_eq!(Zone::from_u8s(&[0]).unwrap(), ALL_ZONES.to_vec());
+        assert_eq!(Zone::from_u8s(&[2, 0]).unwrap(), ALL_ZONES.to_vec());
+        assert_eq!(
+            Zone::from_u8s(&[3, 1]).unwrap(),
+            vec![Zone::new(3).unwrap(), Zone::new(1).unwrap()]
+        );
+        assert!(matches!(
+            Zone::from_u8s(&[1, 5]),
+            Err(Error::InvalidZone(5))
+        ));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{Direction, LightingMode};
 use color_eyre::eyre::Result;
 use std::str::FromStr;
+use std::time::Duration;
 
 pub fn parse_lighting_mode(input: &str) -> Result<LightingMode, String> {
     <LightingMode as FromStr>::from_str(input)
@@ -42,9 +43,29 @@ pub fn parse_confirmation(input: &str) -> Result<bool, String> {
     }
 }
 
-// function to parse color input in either #rrggbb, #rgb, rrggbb, or r,g,b format
+// colors that can be given by name, e.g. --color red
+const NAMED_COLORS: [(&str, (u8, u8, u8)); 10] = [
+    ("black", (0, 0, 0)),
+    ("white", (255, 255, 255)),
+    ("red", (255, 0, 0)),
+    ("green", (0, 255, 0)),
+    ("blue", (0, 0, 255)),
+    ("yellow", (255, 255, 0)),
+    ("cyan", (0, 255, 255)),
+    ("magenta", (255, 0, 255)),
+    ("orange", (255, 96, 0)),
+    ("purple", (128, 0, 255)),
+];
+
+// function to parse color input in either #rrggbb, #rgb, rrggbb, r,g,b format or a color name
 pub fn parse_color(input: &str) -> Result<(u8, u8, u8), String> {
-    if let Some(hex) = input.strip_prefix('#') {
+    if let Some(&(_, color)) = NAMED_COLORS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(input.trim()))
+    {
+        // Handle names like red or orange, before they're mistaken for rrggbb
+        Ok(color)
+    } else if let Some(hex) = input.strip_prefix('#') {
         // Handle #rrggbb or #rgb format
         parse_hex_color(hex)
     } else if input.len() == 6 {
@@ -54,10 +75,23 @@ pub fn parse_color(input: &str) -> Result<(u8, u8, u8), String> {
         // Handle r,g,b format
         parse_rgb_tuple(input)
     } else {
-        Err("Invalid color format. Use #rrggbb, #rgb, rrggbb, or r,g,b.".to_string())
+        Err(
+            "Invalid color format. Use #rrggbb, #rgb, rrggbb, r,g,b or a name like red."
+                .to_string(),
+        )
     }
 }
 
+// function to parse a zone=color pair, e.g. 1=#ff0000 or 3=0,0,255
+pub fn parse_zone_color(input: &str) -> Result<(u8, (u8, u8, u8)), String> {
+    let (zone, color) = input
+        .split_once('=')
+        .ok_or_else(|| "Zone color must be in zone=color format (e.g., 1=#ff0000).".to_string())?;
+    let zone = parse_u8(zone.trim(), "Zone", 1, 4)?;
+    let color = parse_color(color.trim())?;
+    Ok((zone, color))
+}
+
 // helper function to parse rrggbb or #rgb/#rrggbb format
 pub fn parse_hex_color(hex: &str) -> Result<(u8, u8, u8), String> {
     match hex.len() {
@@ -105,24 +139,193 @@ pub fn parse_rgb_tuple(input: &str) -> Result<(u8, u8, u8), String> {
     Ok((red, green, blue))
 }
 
-pub fn preview_static_mode(zones: Vec<u8>, red: u8, green: u8, blue: u8) {
-    let color_code = format!("\x1b[48;2;{};{};{}m \x1b[0m", red, green, blue); // ANSI code for background color
+// function to parse a duration like 300ms, 5s, 1.5m or 2h, plain numbers are seconds
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (value, unit) = input.split_at(split);
+    let value: f64 = value.parse().map_err(|_| {
+        format!(
+            "'{}' is not a valid duration, use e.g. 300ms, 5s or 25m.",
+            input
+        )
+    })?;
+    let seconds = match unit.trim() {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" | "min" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => {
+            return Err(format!(
+                "Unknown duration unit '{}', use ms, s, m or h.",
+                unit
+            ))
+        }
+    };
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|_| format!("'{}' is too long for a duration.", input))
+}
+
+// function to parse progress like 42, 42.5%, or 3/10 into a fraction (0.0 - 1.0)
+pub fn parse_progress(input: &str) -> Result<f64, String> {
+    let input = input.trim();
+    let invalid = || format!("'{}' is not a percentage or current/total.", input);
+    let fraction = match input.split_once('/') {
+        Some((current, total)) => {
+            let current: f64 = current.trim().parse().map_err(|_| invalid())?;
+            let total: f64 = total.trim().parse().map_err(|_| invalid())?;
+            if total <= 0.0 {
+                return Err(format!("The total in '{}' must be above 0.", input));
+            }
+            current / total
+        }
+        None => {
+            let percent: f64 = input
+                .trim_end_matches('%')
+                .trim()
+                .parse()
+                .map_err(|_| invalid())?;
+            percent / 100.0
+        }
+    };
+    match fraction.is_finite() {
+        true => Ok(fraction.clamp(0.0, 1.0)),
+        false => Err(invalid()),
+    }
+}
+
+// function to parse a rate in bytes per second like 500k, 10M, 1.5GB/s or 4096
+pub fn parse_rate(input: &str) -> Result<f64, String> {
+    let input = input.trim();
+    let unit = input.trim_end_matches("/s").trim_end_matches('B');
+    let split = unit
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(unit.len());
+    let (value, prefix) = unit.split_at(split);
+    let value: f64 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a valid rate, use e.g. 500k, 10M or 1G.", input))?;
+    let multiplier = match prefix.trim() {
+        "" => 1.0,
+        "k" | "K" => 1024.0,
+        "M" => 1024.0 * 1024.0,
+        "G" => 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(format!("Unknown rate unit '{}', use k, M or G.", prefix)),
+    };
+    Ok(value * multiplier)
+}
+
+// formats seconds as e.g. 1h 5m, 3m 20s or 45s
+pub fn format_duration(secs: u64) -> String {
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", seconds),
+        (0, _) => format!("{}m {}s", minutes, seconds),
+        _ => format!("{}h {}m", hours, minutes),
+    }
+}
+
+// scales a color by a brightness percentage (0-100)
+pub fn scale_color((red, green, blue): (u8, u8, u8), brightness: u8) -> (u8, u8, u8) {
+    let scale = |c: u8| (c as u16 * brightness as u16 / 100) as u8;
+    (scale(red), scale(green), scale(blue))
+}
+
+pub fn preview_static_mode(zone_colors: &[(u8, (u8, u8, u8))]) {
+    // ANSI code for background color
+    let color_code = |zone: u8| {
+        zone_colors
+            .iter()
+            .find(|(z, _)| *z == zone)
+            .map(|(_, (red, green, blue))| format!("\x1b[48;2;{};{};{}m \x1b[0m", red, green, blue))
+    };
 
     println!("\nPreview of static mode (colored blocks):");
     for zone in 1..=4 {
-        if zones.contains(&zone) {
-            print!("Zone {}: {}\t", zone, color_code);
-        } else {
-            print!("Zone {}: [-]\t", zone);
+        match color_code(zone) {
+            Some(code) => print!("Zone {}: {}\t", zone, code),
+            None => print!("Zone {}: [-]\t", zone),
         }
     }
     println!("\n");
     for zone in 1..=4 {
-        if zones.contains(&zone) {
-            print!("{}{} ", color_code, color_code,);
-        } else {
-            print!("  ");
+        match color_code(zone) {
+            Some(code) => print!("{}{} ", code, code),
+            None => print!("  "),
         }
     }
     println!("\n");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("300ms"), Ok(Duration::from_millis(300)));
+        assert_eq!(parse_duration("5"), Ok(Duration::from_secs(5)));
+        assert_eq!(parse_duration("1.5m"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration(" 2h "), Ok(Duration::from_secs(7200)));
+        assert!(parse_duration("5 days").is_err());
+        assert!(parse_duration("fast").is_err());
+    }
+
+    #[test]
+    fn huge_durations_are_an_error() {
+        assert!(parse_duration("99999999999999999999h").is_err());
+    }
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(parse_color("#ff8000"), Ok((255, 128, 0)));
+        assert_eq!(parse_color("#f80"), Ok((255, 136, 0)));
+        assert_eq!(parse_color("00ff00"), Ok((0, 255, 0)));
+        assert_eq!(parse_color("10, 20, 30"), Ok((10, 20, 30)));
+        assert_eq!(parse_color("Orange"), Ok((255, 96, 0)));
+        assert!(parse_color("#ff80").is_err());
+        assert!(parse_color("256,0,0").is_err());
+        assert!(parse_color("gg0000").is_err());
+        assert!(parse_color("mauve").is_err());
+    }
+
+    #[test]
+    fn parses_zone_colors() {
+        assert_eq!(parse_zone_color("1=#ff0000"), Ok((1, (255, 0, 0))));
+        assert_eq!(parse_zone_color(" 3 = 0,0,255 "), Ok((3, (0, 0, 255))));
+        assert!(parse_zone_color("5=#ff0000").is_err());
+        assert!(parse_zone_color("0=#ff0000").is_err());
+        assert!(parse_zone_color("#ff0000").is_err());
+    }
+
+    #[test]
+    fn scales_colors_by_brightness() {
+        assert_eq!(scale_color((200, 100, 51), 50), (100, 50, 25));
+        assert_eq!(scale_color((255, 255, 255), 100), (255, 255, 255));
+        assert_eq!(scale_color((255, 255, 255), 0), (0, 0, 0));
+    }
+
+    #[test]
+    fn parses_progress() {
+        assert_eq!(parse_progress("42"), Ok(0.42));
+        assert_eq!(parse_progress(" 12.5% "), Ok(0.125));
+        assert_eq!(parse_progress("3/4"), Ok(0.75));
+        assert_eq!(parse_progress("150%"), Ok(1.0));
+        assert_eq!(parse_progress("-5"), Ok(0.0));
+        assert!(parse_progress("3/0").is_err());
+        assert!(parse_progress("NaN").is_err());
+        assert!(parse_progress("done").is_err());
+    }
+
+    #[test]
+    fn parses_rates() {
+        assert_eq!(parse_rate("4096"), Ok(4096.0));
+        assert_eq!(parse_rate("500k"), Ok(512000.0));
+        assert_eq!(parse_rate("1.5GB/s"), Ok(1.5 * 1024.0 * 1024.0 * 1024.0));
+        assert_eq!(parse_rate("10M"), Ok(10.0 * 1024.0 * 1024.0));
+        assert!(parse_rate("10T").is_err());
+        assert!(parse_rate("fast").is_err());
+    }
+}